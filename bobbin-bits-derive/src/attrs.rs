@@ -0,0 +1,135 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::token::Bracket;
+use syn::{Attribute, Error, Expr, ExprLit, ExprRange, Ident, Lit, RangeLimits, Result, Token, Type, TypeArray};
+
+/// Returns the `#[bits(..)]` attribute from a list of attributes, if present.
+pub fn find_bits(attrs: &[Attribute]) -> Option<&Attribute> {
+    attrs.iter().find(|a| a.path().is_ident("bits"))
+}
+
+/// The container of a `BitStruct`.
+pub enum Container {
+    /// A u8, u16, u32 or u64 word.
+    Word(Ident),
+    /// A `[u8; N]` byte array, with fields located by a `BitOrder` constant.
+    Bytes { len: usize, order: Ident },
+}
+
+impl Container {
+    /// Returns the container type.
+    pub fn ty(&self) -> TokenStream {
+        match *self {
+            Container::Word(ref word) => quote!(#word),
+            Container::Bytes { len, .. } => quote!([u8; #len]),
+        }
+    }
+
+    /// Returns the width of the container in bits.
+    pub fn bits(&self) -> TokenStream {
+        match *self {
+            Container::Word(ref word) => quote!(<#word as ::bobbin_bits::Word>::BITS),
+            Container::Bytes { len, .. } => {
+                // parse_container checks that the width fits in a u32.
+                let bits = len as u32 * 8;
+                quote!(#bits)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Container::Word(ref word) => write!(f, "{}", word),
+            Container::Bytes { len, .. } => write!(f, "[u8; {}]", len),
+        }
+    }
+}
+
+/// Parses a `#[bits(u8|u16|u32|u64)]` or `#[bits([u8; N], ORDER)]` container attribute,
+/// where `ORDER` is one of the `BitOrder` constants and defaults to `LE_LSB0`.
+pub fn parse_container(attrs: &[Attribute], span: Span) -> Result<Container> {
+    let attr = find_bits(attrs).ok_or_else(|| {
+        Error::new(span, "expected a #[bits(u8|u16|u32|u64)] or #[bits([u8; N])] attribute")
+    })?;
+    attr.parse_args_with(|input: ParseStream| {
+        if input.peek(Bracket) {
+            let array: TypeArray = input.parse()?;
+            match *array.elem {
+                Type::Path(ref path) if path.path.is_ident("u8") => {}
+                ref elem => return Err(Error::new_spanned(elem, "expected a [u8; N] array")),
+            }
+            let len = parse_int::<usize>(&array.len)?;
+            if len == 0 {
+                return Err(Error::new_spanned(&array.len, "expected a non-empty array"));
+            }
+            if u32::try_from(len).ok().and_then(|n| n.checked_mul(8)).is_none() {
+                return Err(Error::new_spanned(&array.len, "array is too large"));
+            }
+            let order = if input.parse::<Option<Token![,]>>()?.is_some() {
+                let order: Ident = input.parse()?;
+                match order.to_string().as_str() {
+                    "LE_LSB0" | "LE_MSB0" | "BE_LSB0" | "BE_MSB0" => order,
+                    _ => return Err(Error::new_spanned(order, "expected one of LE_LSB0, LE_MSB0, BE_LSB0 or BE_MSB0")),
+                }
+            } else {
+                Ident::new("LE_LSB0", Span::call_site())
+            };
+            return Ok(Container::Bytes { len, order });
+        }
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "u8" | "u16" | "u32" | "u64" => Ok(Container::Word(ident)),
+            _ => Err(Error::new_spanned(ident, "expected one of u8, u16, u32, u64 or [u8; N]")),
+        }
+    })
+}
+
+/// Parses a `#[bits(start..end)]`, `#[bits(start..=end)]` or `#[bits(bit)]` field
+/// attribute, returning the half-open range of bits.
+pub fn parse_range(attr: &Attribute) -> Result<(u32, u32)> {
+    let expr: Expr = attr.parse_args()?;
+    match expr {
+        Expr::Range(ExprRange { ref start, ref end, ref limits, .. }) => {
+            let (start, end) = match (start, end) {
+                (Some(start), Some(end)) => (parse_int::<u32>(start)?, parse_int::<u32>(end)?),
+                _ => return Err(Error::new_spanned(&expr, "expected a bounded range")),
+            };
+            let end = match limits {
+                RangeLimits::HalfOpen(_) => end,
+                RangeLimits::Closed(_) => match end.checked_add(1) {
+                    Some(end) => end,
+                    None => return Err(Error::new_spanned(attr, "bit range is out of bounds")),
+                },
+            };
+            if end <= start {
+                return Err(Error::new_spanned(&expr, "expected a non-empty range"));
+            }
+            Ok((start, end))
+        }
+        _ => {
+            let bit = parse_int::<u32>(&expr)?;
+            match bit.checked_add(1) {
+                Some(end) => Ok((bit, end)),
+                None => Err(Error::new_spanned(attr, "bit index is out of bounds")),
+            }
+        }
+    }
+}
+
+/// Parses an integer literal expression.
+pub fn parse_int<N>(expr: &Expr) -> Result<N>
+where
+    N: FromStr,
+    N::Err: Display,
+{
+    match *expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(ref lit), .. }) => lit.base10_parse(),
+        _ => Err(Error::new_spanned(expr, "expected an integer literal")),
+    }
+}
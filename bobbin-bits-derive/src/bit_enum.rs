@@ -0,0 +1,177 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Data, DeriveInput, Error, Fields, Path, Result, Type, Variant};
+
+use crate::attrs;
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "BitEnum does not support generics"));
+    }
+    let variants = match input.data {
+        Data::Enum(ref data) => &data.variants,
+        _ => return Err(Error::new_spanned(name, "BitEnum can only be derived for enums")),
+    };
+    let attr = input.attrs.iter().find(|a| a.path().is_ident("bits")).ok_or_else(|| {
+        Error::new(name.span(), "expected a #[bits(U1..U32)] attribute")
+    })?;
+    let bits: Path = attr.parse_args()?;
+    let width = parse_width(&bits)?;
+
+    let mut fallback = None;
+    let mut values = Vec::new();
+    let mut next = 0u64;
+    for v in variants.iter() {
+        if v.attrs.iter().any(|a| a.path().is_ident("fallback")) {
+            if fallback.is_some() {
+                return Err(Error::new_spanned(v, "only one #[fallback] variant is allowed"));
+            }
+            fallback = Some(parse_fallback(v, &bits)?);
+            continue;
+        }
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(Error::new_spanned(v, "BitEnum variants must be unit variants"));
+        }
+        let value = match v.discriminant {
+            Some((_, ref expr)) => attrs::parse_int::<u64>(expr)?,
+            None => next,
+        };
+        if value >> width != 0 {
+            let msg = format!("value {} does not fit in {} bits", value, width);
+            return Err(Error::new_spanned(v, msg));
+        }
+        if values.iter().any(|&(_, other)| other == value) {
+            return Err(Error::new_spanned(v, format!("duplicate value {}", value)));
+        }
+        values.push((&v.ident, value));
+        next = value + 1;
+    }
+
+    let into_arms = values.iter().map(|&(ident, value)| {
+        let value = value as u32;
+        quote!(#name::#ident => #value,)
+    });
+    let from_arms: Vec<_> = values.iter().map(|&(ident, value)| {
+        let value = value as u32;
+        quote!(#value => #name::#ident,)
+    }).collect();
+    let fallback_into = fallback.map(|ident| quote!(#name::#ident(value) => return value,));
+
+    let complete = values.len() as u64 == 1u64 << width;
+    let from_impl = match fallback {
+        Some(ident) => quote! {
+            impl ::core::convert::From<#bits> for #name {
+                #[inline]
+                fn from(other: #bits) -> Self {
+                    match ::bobbin_bits::BitWidth::bits(other) {
+                        #(#from_arms)*
+                        _ => #name::#ident(other),
+                    }
+                }
+            }
+        },
+        None if complete => {
+            let count = values.len() as u64;
+            let msg = format!("{} does not cover every value of {}", name, quote!(#bits));
+            quote! {
+                const _: () = assert!(
+                    #count == 1u64 << <#bits as ::bobbin_bits::BitWidth>::WIDTH, #msg);
+
+                impl ::core::convert::From<#bits> for #name {
+                    #[inline]
+                    fn from(other: #bits) -> Self {
+                        match ::bobbin_bits::BitWidth::bits(other) {
+                            #(#from_arms)*
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            }
+        }
+        None => quote! {
+            impl ::core::convert::TryFrom<#bits> for #name {
+                type Error = #bits;
+
+                #[inline]
+                fn try_from(other: #bits) -> ::core::result::Result<Self, #bits> {
+                    Ok(match ::bobbin_bits::BitWidth::bits(other) {
+                        #(#from_arms)*
+                        _ => return Err(other),
+                    })
+                }
+            }
+        },
+    };
+
+    // Enums that can represent every value can be used as bit fields directly. BitWidth
+    // requires Copy, so check for it up front to report the missing derive on the enum.
+    let bit_width_impl = if fallback.is_some() || complete {
+        let assert_copy = quote_spanned! {name.span()=>
+            const _: fn() = || {
+                fn assert_copy<T: ::core::marker::Copy>() {}
+                assert_copy::<#name>();
+            };
+        };
+        quote! {
+            #assert_copy
+
+            impl ::bobbin_bits::BitWidth for #name {
+                const WIDTH: u32 = <#bits as ::bobbin_bits::BitWidth>::WIDTH;
+                const MASK: u32 = <#bits as ::bobbin_bits::BitWidth>::MASK;
+
+                #[inline]
+                fn bits(self) -> u32 {
+                    ::bobbin_bits::BitWidth::bits(<#bits>::from(self))
+                }
+
+                #[inline]
+                fn from_bits_truncate(bits: u32) -> Self {
+                    #name::from(<#bits as ::bobbin_bits::BitWidth>::from_bits_truncate(bits))
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        impl ::core::convert::From<#name> for #bits {
+            #[inline]
+            fn from(other: #name) -> Self {
+                let value: u32 = match other {
+                    #(#into_arms)*
+                    #fallback_into
+                };
+                <#bits as ::bobbin_bits::BitWidth>::from_bits_truncate(value)
+            }
+        }
+
+        #from_impl
+
+        #bit_width_impl
+    })
+}
+
+/// Returns the width of a `U1` through `U32` type from its name.
+fn parse_width(path: &Path) -> Result<u32> {
+    let segment = path.segments.last().unwrap();
+    let name = segment.ident.to_string();
+    match name.strip_prefix('U').and_then(|w| w.parse::<u32>().ok()) {
+        Some(width) if (1..=32).contains(&width) => Ok(width),
+        _ => Err(Error::new_spanned(path, "expected one of U1 through U32")),
+    }
+}
+
+fn parse_fallback<'a>(v: &'a Variant, bits: &Path) -> Result<&'a syn::Ident> {
+    if let Fields::Unnamed(ref fields) = v.fields {
+        if fields.unnamed.len() == 1 {
+            if let Type::Path(ref ty) = fields.unnamed[0].ty {
+                if ty.path.segments.last().map(|s| &s.ident) == bits.segments.last().map(|s| &s.ident) {
+                    return Ok(&v.ident);
+                }
+            }
+        }
+    }
+    Err(Error::new_spanned(v, format!("#[fallback] variant must hold a single {}", quote!(#bits))))
+}
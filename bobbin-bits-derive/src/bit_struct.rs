@@ -0,0 +1,207 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Error, Fields, Ident, Result, Type};
+
+use crate::attrs::{self, Container};
+
+struct BitField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    range: Option<(u32, u32)>,
+    offset: Ident,
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "BitStruct does not support generics"));
+    }
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(name, "BitStruct requires named fields")),
+        },
+        _ => return Err(Error::new_spanned(name, "BitStruct can only be derived for structs")),
+    };
+    let container = attrs::parse_container(&input.attrs, name.span())?;
+    let (word, bits) = (container.ty(), container.bits());
+
+    let mut bit_fields = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let range = match attrs::find_bits(&field.attrs) {
+            Some(attr) => Some(attrs::parse_range(attr)?),
+            None => None,
+        };
+        let offset = format_ident!("__BITS_{}_OFFSET", ident.unraw().to_string().to_uppercase());
+        bit_fields.push(BitField { ident, ty: &field.ty, range, offset });
+    }
+
+    // Offsets of fields without an explicit range follow on from the previous field.
+    let mut offset_consts = Vec::new();
+    for (i, f) in bit_fields.iter().enumerate() {
+        let offset = &f.offset;
+        let value = match f.range {
+            Some((start, _)) => quote!(#start),
+            None if i == 0 => quote!(0),
+            None => {
+                let prev = &bit_fields[i - 1];
+                let (prev_offset, prev_ty) = (&prev.offset, prev.ty);
+                quote!(Self::#prev_offset + <#prev_ty as ::bobbin_bits::BitWidth>::WIDTH)
+            }
+        };
+        offset_consts.push(quote! {
+            #[doc(hidden)]
+            const #offset: u32 = #value;
+        });
+    }
+
+    let mut checks = Vec::new();
+    for f in bit_fields.iter() {
+        let (ident, ty, offset) = (f.ident, f.ty, &f.offset);
+        if let Some((start, end)) = f.range {
+            let width = end - start;
+            let msg = format!("field `{}` does not match the width of #[bits({}..{})]", ident, start, end);
+            checks.push(quote! {
+                assert!(<#ty as ::bobbin_bits::BitWidth>::WIDTH == #width, #msg);
+            });
+        }
+        let msg = format!("field `{}` does not fit in {}", ident, container);
+        checks.push(quote! {
+            assert!(#name::#offset + <#ty as ::bobbin_bits::BitWidth>::WIDTH <= #bits, #msg);
+        });
+    }
+    for (i, a) in bit_fields.iter().enumerate() {
+        for b in bit_fields[i + 1..].iter() {
+            let (a_ty, a_offset) = (a.ty, &a.offset);
+            let (b_ty, b_offset) = (b.ty, &b.offset);
+            let msg = format!("fields `{}` and `{}` overlap", a.ident, b.ident);
+            checks.push(quote! {
+                assert!(#name::#a_offset + <#a_ty as ::bobbin_bits::BitWidth>::WIDTH <= #name::#b_offset
+                    || #name::#b_offset + <#b_ty as ::bobbin_bits::BitWidth>::WIDTH <= #name::#a_offset, #msg);
+            });
+        }
+    }
+
+    let reads = bit_fields.iter().map(|f| {
+        let (ident, ty, offset) = (f.ident, f.ty, &f.offset);
+        match container {
+            Container::Word(_) => quote! {
+                #ident: <#ty as ::bobbin_bits::BitWidth>::from_bits_truncate(
+                    (::bobbin_bits::Word::to_u64(bits) >> Self::#offset) as u32),
+            },
+            Container::Bytes { ref order, .. } => quote! {
+                #ident: ::bobbin_bits::ByteFieldExt::get_field::<#ty>(
+                    &bits[..], Self::#offset as usize, ::bobbin_bits::BitOrder::#order),
+            },
+        }
+    });
+    let writes = bit_fields.iter().map(|f| {
+        let (ident, offset) = (f.ident, &f.offset);
+        match container {
+            Container::Word(_) => quote! {
+                bits |= (::bobbin_bits::BitWidth::bits(self.#ident) as u64) << Self::#offset;
+            },
+            Container::Bytes { ref order, .. } => quote! {
+                ::bobbin_bits::ByteFieldExt::set_field(
+                    &mut bits[..], Self::#offset as usize, self.#ident, ::bobbin_bits::BitOrder::#order);
+            },
+        }
+    });
+    let into_bits = match container {
+        Container::Word(_) => quote! {
+            let mut bits: u64 = 0;
+            #(#writes)*
+            <#word as ::bobbin_bits::Word>::from_u64_truncate(bits)
+        },
+        Container::Bytes { len, .. } => quote! {
+            let mut bits = [0u8; #len];
+            #(#writes)*
+            bits
+        },
+    };
+    let accessors = bit_fields.iter().map(|f| {
+        let (ident, ty) = (f.ident, f.ty);
+        let set = format_ident!("set_{}", ident);
+        let with = format_ident!("with_{}", ident);
+        let get_doc = format!("Returns the `{}` field.", ident);
+        let set_doc = format!("Sets the `{}` field.", ident);
+        let with_doc = format!("Returns a copy with the `{}` field replaced by `value`.", ident);
+        quote! {
+            #[doc = #get_doc]
+            #[inline]
+            #vis fn #ident(&self) -> #ty {
+                self.#ident
+            }
+
+            #[doc = #set_doc]
+            #[inline]
+            #vis fn #set(&mut self, value: #ty) {
+                self.#ident = value;
+            }
+
+            #[doc = #with_doc]
+            #[inline]
+            #vis fn #with(mut self, value: #ty) -> Self {
+                self.#ident = value;
+                self
+            }
+        }
+    });
+    let debug_fields = bit_fields.iter().map(|f| {
+        let ident = f.ident;
+        let label = ident.to_string();
+        quote!(.field(#label, &self.#ident))
+    });
+    let label = name.to_string();
+
+    Ok(quote! {
+        impl #name {
+            #(#offset_consts)*
+
+            /// Unpacks the value from its container.
+            #[inline]
+            #vis fn from_bits(bits: #word) -> Self {
+                #name {
+                    #(#reads)*
+                }
+            }
+
+            /// Packs the value into its container.
+            #[inline]
+            #vis fn into_bits(self) -> #word {
+                #into_bits
+            }
+
+            #(#accessors)*
+        }
+
+        const _: () = {
+            #(#checks)*
+        };
+
+        impl ::core::convert::From<#word> for #name {
+            #[inline]
+            fn from(other: #word) -> Self {
+                #name::from_bits(other)
+            }
+        }
+
+        impl ::core::convert::From<#name> for #word {
+            #[inline]
+            fn from(other: #name) -> Self {
+                other.into_bits()
+            }
+        }
+
+        impl ::core::fmt::Debug for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct(#label)
+                    #(#debug_fields)*
+                    .finish()
+            }
+        }
+    })
+}
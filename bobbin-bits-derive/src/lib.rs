@@ -0,0 +1,199 @@
+//! # bobbin-bits-derive
+//!
+//! Derive macros for [bobbin-bits](https://github.com/bobbin-rs/bobbin-bits/).
+//!
+//! ## BitStruct
+//!
+//! `#[derive(BitStruct)]` packs a struct of U1 through U32 fields into a u8, u16, u32 or
+//! u64 container, or a byte array. The container is named with a `#[bits(..)]` attribute on the struct.
+//! Each field may be given an explicit bit range with `#[bits(start..end)]`,
+//! `#[bits(start..=end)]` or `#[bits(bit)]`; fields without an attribute are placed
+//! immediately after the previous field, starting at bit 0.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitStruct;
+//!
+//! #[derive(BitStruct, Clone, Copy, PartialEq)]
+//! #[bits(u32)]
+//! pub struct Ctrl {
+//!     en: U1,
+//!     mode: U2,
+//!     #[bits(4..16)]
+//!     prescale: U12,
+//!     #[bits(31)]
+//!     busy: U1,
+//! }
+//!
+//! let c = Ctrl::from_bits(0x8000_1235);
+//! assert_eq!(c.en(), U1::B1);
+//! assert_eq!(c.mode(), U2::B10);
+//! assert_eq!(c.prescale(), U12::from(0x123u16));
+//! assert_eq!(c.busy(), U1::B1);
+//!
+//! let c = c.with_busy(U1::B0).with_mode(U2::B01);
+//! assert_eq!(c.into_bits(), 0x0000_1233);
+//! assert_eq!(format!("{:?}", c), "Ctrl { en: 0b1, mode: 0b01, prescale: 0x123, busy: 0b0 }");
+//! ```
+//!
+//! The generated code checks at compile time that every field fits within the container,
+//! that explicit ranges match the width of the field type and that no two fields overlap:
+//!
+//! ```compile_fail
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitStruct;
+//!
+//! #[derive(BitStruct, Clone, Copy)]
+//! #[bits(u8)]
+//! struct Overlap {
+//!     #[bits(0..4)]
+//!     a: U4,
+//!     #[bits(2..4)]
+//!     b: U2,
+//! }
+//! # fn main() { Overlap::from_bits(0); }
+//! ```
+//!
+//! ```compile_fail
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitStruct;
+//!
+//! #[derive(BitStruct, Clone, Copy)]
+//! #[bits(u8)]
+//! struct Overflow {
+//!     a: U4,
+//!     b: U5,
+//! }
+//! # fn main() { Overflow::from_bits(0); }
+//! ```
+//!
+//! A `[u8; N]` container packs fields into a byte array, for wire formats and descriptors
+//! with fields that straddle word boundaries. Fields are located with one of the
+//! `BitOrder` constants, `LE_LSB0` by default:
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitStruct;
+//!
+//! // Numbered as in RFC 791
+//! #[derive(BitStruct, Clone, Copy)]
+//! #[bits([u8; 4], BE_MSB0)]
+//! pub struct Ipv4Word0 {
+//!     version: U4,
+//!     ihl: U4,
+//!     tos: U8,
+//!     length: U16,
+//! }
+//!
+//! let w = Ipv4Word0::from_bits([0x45, 0x00, 0x00, 0x54]);
+//! assert_eq!(w.version(), U4::B0100);
+//! assert_eq!(w.length(), U16::from(84u16));
+//! ```
+//!
+//! ## BitEnum
+//!
+//! `#[derive(BitEnum)]` maps the variants of an enum onto the values of a U type named
+//! with a `#[bits(..)]` attribute. Variant values are taken from their discriminants,
+//! which must be integer literals if given.
+//!
+//! `From<Enum> for U` is always generated. If the variants cover every value of the U
+//! type, `From<U> for Enum` is generated as well; otherwise `TryFrom<U> for Enum` is
+//! generated, returning the unrecognized value as the error.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitEnum;
+//! use std::convert::TryFrom;
+//!
+//! #[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+//! #[bits(U2)]
+//! enum Mode {
+//!     Input,
+//!     Output,
+//!     Alt,
+//!     Analog,
+//! }
+//!
+//! assert_eq!(U2::from(Mode::Alt), U2::B10);
+//! assert_eq!(Mode::from(U2::B11), Mode::Analog);
+//!
+//! #[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+//! #[bits(U3)]
+//! enum Speed {
+//!     Low = 0b001,
+//!     High = 0b100,
+//! }
+//!
+//! assert_eq!(Speed::try_from(U3::B100), Ok(Speed::High));
+//! assert_eq!(Speed::try_from(U3::B111), Err(U3::B111));
+//! ```
+//!
+//! A single `#[fallback]` variant holding the U type makes the conversion infallible,
+//! capturing any value not matched by another variant. Rust requires a `#[repr(..)]`
+//! attribute when such an enum also has explicit discriminants.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitEnum;
+//!
+//! #[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+//! #[bits(U3)]
+//! enum Speed {
+//!     Low,
+//!     High,
+//!     #[fallback]
+//!     Reserved(U3),
+//! }
+//!
+//! assert_eq!(Speed::from(U3::B001), Speed::High);
+//! assert_eq!(Speed::from(U3::B110), Speed::Reserved(U3::B110));
+//! assert_eq!(U3::from(Speed::Reserved(U3::B110)), U3::B110);
+//! ```
+//!
+//! Enums with an infallible conversion also implement `BitWidth`, so they can be used
+//! as `Field` and `BitStruct` fields. `BitWidth` requires `Copy`, so such enums must
+//! derive `Clone` and `Copy`:
+//!
+//! ```compile_fail
+//! use bobbin_bits::*;
+//! use bobbin_bits_derive::BitEnum;
+//!
+//! #[derive(BitEnum)]
+//! #[bits(U1)]
+//! enum Level {
+//!     Low,
+//!     High,
+//! }
+//! # fn main() { U1::from(Level::High); }
+//! ```
+
+extern crate proc_macro;
+
+mod attrs;
+mod bit_enum;
+mod bit_struct;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives packing of a struct of U-typed fields into a primitive container.
+///
+/// See the crate documentation for details.
+#[proc_macro_derive(BitStruct, attributes(bits))]
+pub fn derive_bit_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bit_struct::expand(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives conversions between a fieldless enum and a U type.
+///
+/// See the crate documentation for details.
+#[proc_macro_derive(BitEnum, attributes(bits, fallback))]
+pub fn derive_bit_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bit_enum::expand(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -0,0 +1,81 @@
+use std::convert::TryFrom;
+
+use bobbin_bits::*;
+use bobbin_bits_derive::{BitEnum, BitStruct};
+
+#[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+#[bits(U2)]
+enum Mode {
+    Input,
+    Output,
+    Alt,
+    Analog,
+}
+
+#[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+#[bits(U2)]
+enum Pull {
+    None = 0b00,
+    Up = 0b01,
+    Down = 0b10,
+}
+
+#[derive(BitEnum, Clone, Copy, Debug, PartialEq)]
+#[bits(U3)]
+#[repr(u8)]
+enum Speed {
+    Low = 0b001,
+    High = 0b100,
+    #[fallback]
+    Other(U3),
+}
+
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits(u8)]
+struct Pin {
+    mode: Mode,
+    #[bits(4..7)]
+    speed: Speed,
+}
+
+#[test]
+fn test_complete() {
+    for (m, u) in [
+        (Mode::Input, U2::B00),
+        (Mode::Output, U2::B01),
+        (Mode::Alt, U2::B10),
+        (Mode::Analog, U2::B11),
+    ] {
+        assert_eq!(U2::from(m), u);
+        assert_eq!(Mode::from(u), m);
+    }
+}
+
+#[test]
+fn test_partial() {
+    assert_eq!(U2::from(Pull::Down), U2::B10);
+    assert_eq!(Pull::try_from(U2::B00), Ok(Pull::None));
+    assert_eq!(Pull::try_from(U2::B01), Ok(Pull::Up));
+    assert_eq!(Pull::try_from(U2::B11), Err(U2::B11));
+}
+
+#[test]
+fn test_fallback() {
+    assert_eq!(Speed::from(U3::B001), Speed::Low);
+    assert_eq!(Speed::from(U3::B100), Speed::High);
+    assert_eq!(Speed::from(U3::B000), Speed::Other(U3::B000));
+    assert_eq!(U3::from(Speed::High), U3::B100);
+    assert_eq!(U3::from(Speed::Other(U3::B111)), U3::B111);
+}
+
+#[test]
+fn test_as_field() {
+    assert_eq!(<Mode as BitWidth>::WIDTH, 2);
+    assert_eq!(0b1100u8.get_field::<Mode>(2), Mode::Analog);
+    assert_eq!(Field::<1, Mode>::write(0u8, Mode::Alt), 0b100);
+
+    let p = Pin::from_bits(0b0100_0001);
+    assert_eq!(p.mode(), Mode::Output);
+    assert_eq!(p.speed(), Speed::High);
+    assert_eq!(p.with_speed(Speed::Other(U3::B111)).into_bits(), 0b0111_0001);
+}
@@ -0,0 +1,127 @@
+use bobbin_bits::*;
+use bobbin_bits_derive::BitStruct;
+
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits(u8)]
+struct Status {
+    ready: U1,
+    error: U1,
+    #[bits(4..=7)]
+    code: U4,
+}
+
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits(u32)]
+struct Header {
+    version: U2,
+    kind: U3,
+    length: U11,
+    sequence: U16,
+}
+
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits(u64)]
+struct Wide {
+    #[bits(0..32)]
+    lo: U32,
+    #[bits(40..64)]
+    hi: U24,
+}
+
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits(u8)]
+struct Keywords {
+    r#type: U4,
+    r#match: U4,
+}
+
+#[test]
+fn test_explicit_and_sequential() {
+    let s = Status::from_bits(0b1010_0110);
+    assert_eq!(s.ready(), U1::B0);
+    assert_eq!(s.error(), U1::B1);
+    assert_eq!(s.code(), U4::B1010);
+    // Bits outside of any field are discarded
+    assert_eq!(s.into_bits(), 0b1010_0010);
+
+    let mut s = s;
+    s.set_ready(U1::B1);
+    s.set_code(U4::B0001);
+    assert_eq!(u8::from(s), 0b0001_0011);
+    assert!(Status::from(0b0001_0011u8) == s);
+}
+
+#[test]
+fn test_round_trip() {
+    let h = Header::from_bits(0xabcd_1234);
+    assert_eq!(h.version(), U2::B00);
+    assert_eq!(h.kind(), U3::B101);
+    assert_eq!(h.length(), U11::from(0x091u16));
+    assert_eq!(h.sequence(), U16::from(0xabcdu16));
+    assert_eq!(h.into_bits(), 0xabcd_1234);
+
+    let h = h.with_sequence(U16::from(0u8)).with_version(U2::B11);
+    assert_eq!(h.into_bits(), 0x0000_1237);
+}
+
+#[test]
+fn test_raw_identifiers() {
+    let k = Keywords::from_bits(0x5a).with_match(U4::B0011);
+    assert_eq!(k.r#type(), U4::B1010);
+    assert_eq!(k.into_bits(), 0x3a);
+}
+
+#[test]
+fn test_u64_container() {
+    let w = Wide::from_bits(0xffff_ff12_8765_4321);
+    assert_eq!(w.lo(), U32::from(0x8765_4321u32));
+    assert_eq!(w.hi(), U24::from(0xff_ffffu32));
+    assert_eq!(w.into_bits(), 0xffff_ff00_8765_4321);
+}
+
+#[test]
+fn test_debug() {
+    let s = Status::from_bits(0b0011_0001);
+    assert_eq!(format!("{:?}", s), "Status { ready: 0b1, error: 0b0, code: 0b0011 }");
+}
+
+// A UDP header, numbered as in RFC 768.
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits([u8; 8], BE_MSB0)]
+struct Udp {
+    source: U16,
+    destination: U16,
+    length: U16,
+    checksum: U16,
+}
+
+// A descriptor with fields straddling u32 boundaries.
+#[derive(BitStruct, Clone, Copy, PartialEq)]
+#[bits([u8; 6])]
+struct Descriptor {
+    #[bits(0..4)]
+    kind: U4,
+    #[bits(20..44)]
+    address: U24,
+    #[bits(47)]
+    last: U1,
+}
+
+#[test]
+fn test_byte_array_container() {
+    let bytes = [0x04, 0xd2, 0x00, 0x35, 0x00, 0x1c, 0xab, 0xcd];
+    let u = Udp::from_bits(bytes);
+    assert_eq!(u.source(), U16::from(1234u16));
+    assert_eq!(u.destination(), U16::from(53u16));
+    assert_eq!(u.length(), U16::from(28u16));
+    assert_eq!(u.checksum(), U16::from(0xabcdu16));
+    assert_eq!(u.into_bits(), bytes);
+    assert_eq!(<[u8; 8]>::from(u.with_length(U16::from(0x1234u16)))[4..6], [0x12, 0x34]);
+
+    let d = Descriptor::from_bits([0x0f, 0, 0x60, 0x45, 0x23, 0x80]);
+    assert_eq!(d.kind(), U4::B1111);
+    assert_eq!(d.address(), U24::from(0x02_3456u32));
+    assert_eq!(d.last(), U1::B1);
+    let d = d.with_address(U24::from(0xff_ffffu32));
+    assert_eq!(d.into_bits(), [0x0f, 0, 0xf0, 0xff, 0xff, 0x8f]);
+}
@@ -0,0 +1,60 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use bobbin_bits_gen::layout::Layout;
+
+const USAGE: &str = "usage: bobbin-bits-gen [--c] <input.svd | input.layout> [-o <output>]";
+
+fn main() {
+    let mut input = None;
+    let mut output = None;
+    let mut c = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--c" => c = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if input.is_none() && !arg.starts_with('-') => input = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let input = input.unwrap_or_else(|| fail(USAGE));
+
+    let text = fs::read_to_string(&input).unwrap_or_else(|e| fail(&format!("{}: {}", input, e)));
+    let code = if input.ends_with(".layout") {
+        let layout = Layout::parse(&text).unwrap_or_else(|e| fail(&format!("{}: {}", input, e)));
+        if c {
+            bobbin_bits_gen::c::generate(&layout, &guard(output.as_deref().unwrap_or(&input)))
+        } else {
+            bobbin_bits_gen::rust::generate_layout(&layout)
+        }
+    } else {
+        if c {
+            fail("C headers are generated from layout files");
+        }
+        let device = bobbin_bits_gen::svd::parse(&text).unwrap_or_else(|e| fail(&format!("{}: {}", input, e)));
+        bobbin_bits_gen::rust::generate(&device)
+    };
+    match output {
+        Some(output) => fs::write(&output, code).unwrap_or_else(|e| fail(&format!("{}: {}", output, e))),
+        None => print!("{}", code),
+    }
+}
+
+/// Returns the include guard for a header: `uart.h` becomes `UART_H`.
+fn guard(path: &str) -> String {
+    let stem = Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or("layout");
+    let stem: String = stem.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    format!("{}_H", stem)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
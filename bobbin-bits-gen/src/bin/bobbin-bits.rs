@@ -0,0 +1,116 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use bobbin_bits_gen::layout::Layout;
+use bobbin_bits_gen::model::{parse_number, Register};
+use bobbin_bits_gen::report;
+
+const USAGE: &str = "\
+usage: bobbin-bits <command> [options] <args>...
+
+commands:
+  decode <value>                   show the fields of a value
+  encode [<value>] <field>=<v>...  set fields, starting from the reset value or <value>
+  diff <a> <b>                     show the fields that differ between two values
+
+options:
+  -l, --layout <file>              read fields from a layout file
+  -w, --word <name>                select a word of the layout (default: the only word)
+  -f, --field <name:offset:width>  define a field inline; may be repeated
+  -s, --size <bits>                word size for inline fields (default: large enough for the fields and values)";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| fail(USAGE));
+    let mut layout = None;
+    let mut word = None;
+    let mut specs = Vec::new();
+    let mut size = None;
+    let mut operands = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(USAGE));
+        match arg.as_str() {
+            "-l" | "--layout" => layout = Some(value()),
+            "-w" | "--word" => word = Some(value()),
+            "-f" | "--field" => specs.push(value()),
+            "-s" | "--size" => size = Some(value().parse::<u32>().unwrap_or_else(|_| fail(USAGE))),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => operands.push(arg),
+        }
+    }
+    if command == "-h" || command == "--help" {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let number = |text: &str| parse_number(text).unwrap_or_else(|e| fail(&e.to_string()));
+    let values: Vec<u64> = operands.iter().filter(|o| !o.contains('=')).map(|o| number(o)).collect();
+
+    let layout = match layout {
+        Some(path) => {
+            let text = fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+            Layout::parse(&text).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+        }
+        None if !specs.is_empty() => {
+            let size = size.unwrap_or_else(|| inline_size(&specs, &values));
+            Layout::from_specs(&specs, size).unwrap_or_else(|e| fail(&e.to_string()))
+        }
+        None => fail("no fields: use --layout or --field"),
+    };
+    let register = match word {
+        Some(name) => layout.word(&name).unwrap_or_else(|| fail(&format!("no word {}", name))),
+        None if layout.words().len() == 1 => &layout.words()[0],
+        None => fail("the layout has several words: use --word"),
+    };
+    for &value in &values {
+        if register.size < 64 && value >> register.size != 0 {
+            fail(&format!("{:#x} does not fit in {}-bit word {}", value, register.size, register.name));
+        }
+    }
+
+    match (command.as_str(), &values[..]) {
+        ("decode", &[value]) if operands.len() == 1 => print!("{}", report::decode(register, value)),
+        ("encode", _) if values.len() <= 1 => print!("{}", report::decode(register, encode(register, &operands))),
+        ("diff", &[a, b]) if operands.len() == 2 => print!("{}", report::diff(register, a, b)),
+        _ => fail(USAGE),
+    }
+}
+
+fn encode(register: &Register, operands: &[String]) -> u64 {
+    let mut word = register.reset_value;
+    for operand in operands {
+        match operand.split_once('=') {
+            Some((name, value)) => {
+                let field = register
+                    .field(name)
+                    .unwrap_or_else(|| fail(&format!("{}: no field {}", register.name, name)));
+                let value = field.parse_value(value).unwrap_or_else(|e| fail(&e.to_string()));
+                word = register.encode(word, name, value).unwrap_or_else(|e| fail(&e.to_string()));
+            }
+            None => word = parse_number(operand).unwrap_or_else(|e| fail(&e.to_string())),
+        }
+    }
+    word
+}
+
+/// Returns the smallest word size holding every inline field and value.
+fn inline_size(specs: &[String], values: &[u64]) -> u32 {
+    let fields = specs.iter().filter_map(|spec| {
+        let mut parts = spec.split(':').skip(1).map(|p| parse_number(p).ok());
+        let (offset, width) = (parts.next()??, parts.next()??);
+        let end = offset.checked_add(width);
+        Some(end.unwrap_or_else(|| fail(&format!("invalid field spec {:?}, offset plus width overflows", spec))))
+    });
+    let values = values.iter().map(|v| 64 - v.leading_zeros() as u64);
+    let bits = fields.chain(values).max().unwrap_or(0);
+    [8, 16, 32, 64].into_iter().find(|&size| bits <= size).unwrap_or(64) as u32
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,173 @@
+//! C header generation.
+//!
+//! For every word of a layout, the header defines `<WORD>_OFFSET` and `<WORD>_RESET`, and
+//! for every field `<WORD>_<FIELD>_Pos` and `<WORD>_<FIELD>_Msk`, an inline
+//! `<word>_<field>_get()` function for readable fields, an inline `<word>_<field>_set()`
+//! function for writable fields and a `<WORD>_<FIELD>_<VALUE>` macro for every value
+//! name. Field values use the smallest of `uint8_t`, `uint16_t` and `uint32_t` that holds
+//! them, matching the U type used on the Rust side.
+//!
+//! ```
+//! use bobbin_bits_gen::c;
+//! use bobbin_bits_gen::layout::Layout;
+//!
+//! let layout = Layout::parse("
+//!     word CTRL: u32
+//!         MODE [2:1] { Input = 0, Output = 1 }
+//! ").unwrap();
+//!
+//! let header = c::generate(&layout, "CTRL_H");
+//! assert!(header.contains("#define CTRL_MODE_Pos 1U\n"));
+//! assert!(header.contains("#define CTRL_MODE_Msk (0x3UL << CTRL_MODE_Pos)\n"));
+//! assert!(header.contains("#define CTRL_MODE_OUTPUT 0x1U\n"));
+//! assert!(header.contains("static inline uint8_t ctrl_mode_get(uint32_t reg)"));
+//! ```
+
+use crate::layout::Layout;
+use crate::model::{Field, Register};
+
+/// Generates a C header for a layout, guarded by the macro `guard`.
+pub fn generate(layout: &Layout, guard: &str) -> String {
+    let mut out = String::new();
+    out.push_str("/* Generated by bobbin-bits-gen. Do not edit. */\n\n");
+    out.push_str(&format!("#ifndef {0}\n#define {0}\n\n#include <stdint.h>\n", guard));
+    for word in layout.words() {
+        out.push('\n');
+        self::word(&mut out, word);
+    }
+    out.push_str(&format!("\n#endif /* {} */\n", guard));
+    out
+}
+
+/// Returns the C type holding `bits` bits.
+fn c_type(bits: u32) -> &'static str {
+    match bits {
+        0..=8 => "uint8_t",
+        9..=16 => "uint16_t",
+        17..=32 => "uint32_t",
+        _ => "uint64_t",
+    }
+}
+
+/// Returns the integer suffix for a constant of the word size.
+fn suffix(size: u32) -> &'static str {
+    if size > 32 {
+        "ULL"
+    } else {
+        "UL"
+    }
+}
+
+/// Returns a C identifier for `name`, with the words of the name joined by underscores.
+///
+/// Unlike the Rust identifiers, names that are only reserved in Rust are kept as written;
+/// only a leading digit, which C rejects, is prefixed with an underscore.
+fn ident(name: &str, upper: bool) -> String {
+    let words: Vec<_> = name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    let mut ident = words.join("_");
+    if upper {
+        ident.make_ascii_uppercase();
+    } else {
+        ident.make_ascii_lowercase();
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn upper(name: &str) -> String {
+    ident(name, true)
+}
+
+fn lower(name: &str) -> String {
+    ident(name, false)
+}
+
+fn comment(out: &mut String, text: Option<&str>) {
+    if let Some(text) = text {
+        out.push_str(&format!("/* {} */\n", text.replace("*/", "* /")));
+    }
+}
+
+fn word(out: &mut String, w: &Register) {
+    let name = upper(&w.name);
+    comment(out, w.description.as_deref());
+    out.push_str(&format!("#define {}_OFFSET 0x{:x}U\n", name, w.address_offset));
+    out.push_str(&format!("#define {}_RESET 0x{:x}{}\n", name, w.reset_value, suffix(w.size)));
+    for f in &w.fields {
+        out.push('\n');
+        field(out, w, f);
+    }
+}
+
+fn field(out: &mut String, w: &Register, f: &Field) {
+    let name = format!("{}_{}", upper(&w.name), upper(&f.name));
+    let func = format!("{}_{}", lower(&w.name), lower(&f.name));
+    let (reg, value) = (c_type(w.size), c_type(f.width));
+    let ones = u64::MAX >> (64 - f.width);
+    comment(out, f.description.as_deref());
+    out.push_str(&format!("#define {}_Pos {}U\n", name, f.offset));
+    out.push_str(&format!("#define {0}_Msk (0x{1:x}{2} << {0}_Pos)\n", name, ones, suffix(w.size)));
+    for v in &f.values {
+        out.push_str(&format!("#define {}_{} 0x{:x}U\n", name, upper(&v.name), v.value));
+    }
+    if f.access.is_readable() {
+        out.push_str(&format!(
+            "static inline {value} {func}_get({reg} reg) {{ return ({value})((reg & {name}_Msk) >> {name}_Pos); }}\n",
+        ));
+    }
+    if f.access.is_writable() {
+        out.push_str(&format!(
+            "static inline {reg} {func}_set({reg} reg, {value} value) {{ \
+             return ({reg})((reg & ~{name}_Msk) | ((({reg})value << {name}_Pos) & {name}_Msk)); }}\n",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_type() {
+        assert_eq!(c_type(1), "uint8_t");
+        assert_eq!(c_type(8), "uint8_t");
+        assert_eq!(c_type(9), "uint16_t");
+        assert_eq!(c_type(32), "uint32_t");
+        assert_eq!(c_type(64), "uint64_t");
+    }
+
+    #[test]
+    fn test_access() {
+        let layout = Layout::parse(
+            "word STATUS: u16
+                READY [0] ro
+                START [1] wo
+                OVR [2] w1c",
+        )
+        .unwrap();
+        let header = generate(&layout, "STATUS_H");
+        assert!(header.contains("status_ready_get("));
+        assert!(!header.contains("status_ready_set("));
+        assert!(!header.contains("status_start_get("));
+        assert!(header.contains("status_start_set("));
+        assert!(header.contains("status_ovr_get("));
+        assert!(header.contains("status_ovr_set("));
+    }
+
+    #[test]
+    fn test_names() {
+        let layout = Layout::parse(
+            "word CTRL: u32
+                RW [0]
+                R1 [1]
+                type [2]",
+        )
+        .unwrap();
+        let header = generate(&layout, "CTRL_H");
+        assert!(header.contains("#define CTRL_RW_Pos 0U\n"));
+        assert!(header.contains("#define CTRL_R1_Msk (0x1UL << CTRL_R1_Pos)\n"));
+        assert!(header.contains("ctrl_type_get("));
+    }
+}
@@ -0,0 +1,81 @@
+//! Conversion of device names into Rust identifiers.
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self", "static",
+    "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
+/// Names imported from bobbin-bits or defined by the generated code.
+const RESERVED: &[&str] = &[
+    "BitEnum", "Field", "Register", "RegisterSpec", "Spec", "RO", "WO", "RW", "W1C", "W1S", "RC",
+];
+
+fn words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty())
+}
+
+fn fixup(mut ident: String, suffix: &str) -> String {
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    let numbered = |p: char| ident.strip_prefix(p).is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    if KEYWORDS.contains(&ident.as_str()) || RESERVED.contains(&ident.as_str()) || numbered('U') || numbered('R') {
+        ident.push_str(suffix);
+    }
+    ident
+}
+
+/// Returns a `snake_case` identifier for modules and functions.
+pub fn snake(name: &str) -> String {
+    fixup(words(name).collect::<Vec<_>>().join("_").to_ascii_lowercase(), "_")
+}
+
+/// Returns an `UPPER_CASE` identifier for fields.
+pub fn upper(name: &str) -> String {
+    fixup(words(name).collect::<Vec<_>>().join("_").to_ascii_uppercase(), "_")
+}
+
+/// Returns a `CamelCase` identifier for enums and variants.
+///
+/// Words written entirely in upper case are capitalized; other words keep their case.
+pub fn camel(name: &str) -> String {
+    let mut ident = String::new();
+    for word in words(name) {
+        let mut chars = word.chars();
+        let first = chars.next().unwrap();
+        ident.push(first.to_ascii_uppercase());
+        if word.bytes().any(|b| b.is_ascii_lowercase()) {
+            ident.extend(chars);
+        } else {
+            ident.extend(chars.map(|c| c.to_ascii_lowercase()));
+        }
+    }
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, 'V');
+    }
+    fixup(ident, "Value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idents() {
+        assert_eq!(snake("GPIOA"), "gpioa");
+        assert_eq!(snake("CH.CR"), "ch_cr");
+        assert_eq!(snake("TYPE"), "type_");
+        assert_eq!(snake("3V3"), "_3v3");
+        assert_eq!(upper("pin_mode"), "PIN_MODE");
+        assert_eq!(upper("U1"), "U1_");
+        assert_eq!(camel("PIN_MODE"), "PinMode");
+        assert_eq!(camel("pushPull"), "PushPull");
+        assert_eq!(camel("1_BIT"), "V1Bit");
+        assert_eq!(camel("SPEC"), "SpecValue");
+        assert_eq!(camel("self"), "SelfValue");
+        assert_eq!(camel("R12"), "R12Value");
+    }
+}
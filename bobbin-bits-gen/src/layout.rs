@@ -0,0 +1,316 @@
+//! A hand-editable layout format.
+//!
+//! A layout describes a set of words, each made up of named bit fields:
+//!
+//! ```text
+//! # Comments start with '#'. Comments starting with '///' document the next word or field.
+//!
+//! /// Control register
+//! word CTRL @ 0x00: u32 = 0x0000_0000
+//!     /// Enable
+//!     EN [0]
+//!     MODE [2:1] rw { Input = 0, Output = 1, Alt = 2, Analog = 3 }
+//!     PRESCALE [15:4]
+//!     BUSY [31] ro
+//!
+//! word STATUS @ 0x04: u16
+//!     READY [0] ro
+//!     OVR [1] w1c
+//! ```
+//!
+//! A word is declared with its name, an optional byte offset, its size (`u8`, `u16`, `u32`
+//! or `u64`) and an optional reset value. Each following line declares a field of the word
+//! with its name, its bits as `[msb:lsb]` or `[bit]`, an optional access mode (`ro`, `wo`,
+//! `rw`, `w1c`, `w1s` or `rc`; the default is `rw`) and optional value names. Numbers may
+//! be decimal, `0x` hexadecimal or `0b` binary, with `_` separators.
+//!
+//! Loading checks that every field width maps to a U type, that fields fit within their
+//! word and that no two fields overlap. The resulting [`Layout`] decodes and encodes
+//! values at run time:
+//!
+//! ```
+//! use bobbin_bits_gen::layout::Layout;
+//!
+//! let layout = Layout::parse("
+//!     word CTRL: u32
+//!         EN [0]
+//!         MODE [2:1] { Input = 0, Output = 1, Alt = 2, Analog = 3 }
+//! ").unwrap();
+//!
+//! let ctrl = layout.word("CTRL").unwrap();
+//! let fields: Vec<_> = ctrl.decode(0x5).iter().map(|(f, v)| (f.name.as_str(), *v)).collect();
+//! assert_eq!(fields, [("EN", 1), ("MODE", 2)]);
+//! assert_eq!(ctrl.field("MODE").unwrap().value_name(2), Some("Alt"));
+//! assert_eq!(ctrl.encode(0x0, "MODE", 3).unwrap(), 0x6);
+//! ```
+
+use bobbin_bits::AccessKind;
+
+use crate::model::{parse_number, EnumValue, Field, Register};
+use crate::Error;
+
+/// A set of words described by a layout file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    words: Vec<Register>,
+}
+
+impl Layout {
+    /// Parses and validates a layout.
+    pub fn parse(text: &str) -> Result<Layout, Error> {
+        let mut words: Vec<Register> = Vec::new();
+        let mut doc: Vec<&str> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let context = |e: Error| e.context(&format!("line {}", i + 1));
+            let line = line.trim();
+            if let Some(d) = line.strip_prefix("///") {
+                doc.push(d.trim());
+                continue;
+            }
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let description = if doc.is_empty() { None } else { Some(doc.join(" ")) };
+            doc.clear();
+            if let Some(rest) = line.strip_prefix("word ") {
+                words.push(Register { description, ..word(rest).map_err(context)? });
+            } else {
+                let w = words.last_mut().ok_or_else(|| context(Error::new("field outside of a word")))?;
+                w.fields.push(Field { description, ..field(line).map_err(context)? });
+            }
+        }
+        Layout::new(words)
+    }
+
+    /// Constructs a layout from a list of words, validating each of them.
+    pub fn new(words: Vec<Register>) -> Result<Layout, Error> {
+        for (i, w) in words.iter().enumerate() {
+            w.validate()?;
+            if words[..i].iter().any(|v| v.name == w.name) {
+                return Err(Error::new(format!("{}: duplicate word", w.name)));
+            }
+            for (j, f) in w.fields.iter().enumerate() {
+                if w.fields[..j].iter().any(|g| g.name == f.name) {
+                    return Err(Error::new(format!("{}: duplicate field {}", w.name, f.name)));
+                }
+            }
+        }
+        Ok(Layout { words })
+    }
+
+    /// Constructs a layout with a single word named `value` from `name:offset:width` field
+    /// specs.
+    pub fn from_specs<S: AsRef<str>>(specs: &[S], size: u32) -> Result<Layout, Error> {
+        let mut fields = Vec::new();
+        for spec in specs {
+            let spec = spec.as_ref();
+            let parts: Vec<&str> = spec.split(':').collect();
+            let (name, offset, width) = match parts[..] {
+                [name, offset, width] => (identifier(name)?, parse_number(offset)?, parse_number(width)?),
+                _ => return Err(Error::new(format!("invalid field spec {:?}, expected name:offset:width", spec))),
+            };
+            if offset >= 64 || !(1..=64).contains(&width) {
+                return Err(Error::new(format!("{}: invalid bit position", name)));
+            }
+            fields.push(Field {
+                name: name.to_string(),
+                description: None,
+                offset: offset as u32,
+                width: width as u32,
+                access: AccessKind::ReadWrite,
+                values: Vec::new(),
+            });
+        }
+        fields.sort_by_key(|f| f.offset);
+        Layout::new(vec![Register {
+            name: String::from("value"),
+            description: None,
+            address_offset: 0,
+            size,
+            reset_value: 0,
+            access: AccessKind::ReadWrite,
+            dim: None,
+            fields,
+        }])
+    }
+
+    /// Returns the words of the layout.
+    pub fn words(&self) -> &[Register] {
+        &self.words
+    }
+
+    /// Returns the word named `name`.
+    pub fn word(&self, name: &str) -> Option<&Register> {
+        self.words.iter().find(|w| w.name == name)
+    }
+}
+
+/// Parses `NAME [@ OFFSET]: SIZE [= RESET]`.
+fn word(text: &str) -> Result<Register, Error> {
+    let (head, tail) = text.split_once(':').ok_or_else(|| Error::new("expected ':' after word name"))?;
+    let (name, offset) = match head.split_once('@') {
+        Some((name, offset)) => (name.trim(), parse_number(offset)?),
+        None => (head.trim(), 0),
+    };
+    let (size, reset) = match tail.split_once('=') {
+        Some((size, reset)) => (size.trim(), parse_number(reset)?),
+        None => (tail.trim(), 0),
+    };
+    let size = match size {
+        "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        "u64" => 64,
+        _ => return Err(Error::new(format!("{}: unknown word size {:?}", name, size))),
+    };
+    Ok(Register {
+        name: identifier(name)?.to_string(),
+        description: None,
+        address_offset: offset,
+        size,
+        reset_value: reset,
+        access: AccessKind::ReadWrite,
+        dim: None,
+        fields: Vec::new(),
+    })
+}
+
+/// Parses `NAME [MSB:LSB] [ACCESS] [{ VALUE = N, ... }]`.
+fn field(text: &str) -> Result<Field, Error> {
+    let (text, values) = match text.split_once('{') {
+        Some((text, values)) => {
+            let values = values.trim().strip_suffix('}').ok_or_else(|| Error::new("expected '}'"))?;
+            (text, Some(values))
+        }
+        None => (text, None),
+    };
+    let (name, rest) = text.split_once('[').ok_or_else(|| Error::new("expected '[' after field name"))?;
+    let name = identifier(name.trim())?;
+    let (bits, access) = rest.split_once(']').ok_or_else(|| Error::new(format!("{}: expected ']'", name)))?;
+    let (msb, lsb) = match bits.split_once(':') {
+        Some((msb, lsb)) => (parse_number(msb)?, parse_number(lsb)?),
+        None => (parse_number(bits)?, parse_number(bits)?),
+    };
+    if msb < lsb || msb >= 64 {
+        return Err(Error::new(format!("{}: invalid bits [{}]", name, bits.trim())));
+    }
+    let access = match access.trim() {
+        "" | "rw" => AccessKind::ReadWrite,
+        "ro" => AccessKind::ReadOnly,
+        "wo" => AccessKind::WriteOnly,
+        "w1c" => AccessKind::WriteOneToClear,
+        "w1s" => AccessKind::WriteOneToSet,
+        "rc" => AccessKind::ReadToClear,
+        a => return Err(Error::new(format!("{}: unknown access mode {:?}", name, a))),
+    };
+    let mut field = Field {
+        name: name.to_string(),
+        description: None,
+        offset: lsb as u32,
+        width: (msb - lsb + 1) as u32,
+        access,
+        values: Vec::new(),
+    };
+    for value in values.iter().flat_map(|v| v.split(',')).map(str::trim).filter(|v| !v.is_empty()) {
+        let (n, v) = value.split_once('=').ok_or_else(|| Error::new(format!("{}: expected NAME = VALUE", name)))?;
+        let (n, v) = (identifier(n.trim())?, parse_number(v)?);
+        if field.values.iter().any(|e| e.name == n || e.value == v) {
+            return Err(Error::new(format!("{}: duplicate value {}", name, n)));
+        }
+        field.values.push(EnumValue { name: n.to_string(), description: None, value: v });
+    }
+    Ok(field)
+}
+
+fn identifier(name: &str) -> Result<&str, Error> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(name)
+    } else {
+        Err(Error::new(format!("invalid name {:?}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+        # Example layout
+
+        /// Control register
+        word CTRL @ 0x00: u32 = 0x8000_0000
+            /// Enable
+            EN [0]
+            MODE [2:1] rw { Input = 0, Output = 1, Alt = 2, Analog = 3 }
+            PRESCALE [15:4]   # divided by PRESCALE + 1
+            BUSY [31] ro
+
+        word STATUS @ 0x04: u16
+            READY [0] ro
+            OVR [1] w1c
+    ";
+
+    #[test]
+    fn test_parse() {
+        let layout = Layout::parse(EXAMPLE).unwrap();
+        assert_eq!(layout.words().len(), 2);
+
+        let ctrl = layout.word("CTRL").unwrap();
+        assert_eq!(ctrl.description.as_deref(), Some("Control register"));
+        assert_eq!((ctrl.address_offset, ctrl.size, ctrl.reset_value), (0, 32, 0x8000_0000));
+        let fields: Vec<_> = ctrl.fields.iter().map(|f| (f.name.as_str(), f.offset, f.width, f.access)).collect();
+        assert_eq!(
+            fields,
+            [
+                ("EN", 0, 1, AccessKind::ReadWrite),
+                ("MODE", 1, 2, AccessKind::ReadWrite),
+                ("PRESCALE", 4, 12, AccessKind::ReadWrite),
+                ("BUSY", 31, 1, AccessKind::ReadOnly),
+            ]
+        );
+        assert_eq!(ctrl.fields[0].description.as_deref(), Some("Enable"));
+        assert_eq!(ctrl.fields[1].values.len(), 4);
+        assert_eq!(ctrl.fields[1].values[3].name, "Analog");
+
+        let status = layout.word("STATUS").unwrap();
+        assert_eq!((status.address_offset, status.size), (4, 16));
+        assert_eq!(status.fields[1].access, AccessKind::WriteOneToClear);
+        assert!(layout.word("DATA").is_none());
+    }
+
+    #[test]
+    fn test_from_specs() {
+        let layout = Layout::from_specs(&["mode:4:2", "en:0:1"], 8).unwrap();
+        let word = &layout.words()[0];
+        assert_eq!(word.name, "value");
+        let fields: Vec<_> = word.fields.iter().map(|f| (f.name.as_str(), f.offset, f.width)).collect();
+        assert_eq!(fields, [("en", 0, 1), ("mode", 4, 2)]);
+        let e = Layout::from_specs(&["en:0"], 8).unwrap_err();
+        assert_eq!(e.to_string(), "invalid field spec \"en:0\", expected name:offset:width");
+        let e = Layout::from_specs(&["a:0:4", "b:2:4"], 8).unwrap_err();
+        assert_eq!(e.to_string(), "value: fields a and b overlap");
+    }
+
+    fn error(text: &str) -> String {
+        Layout::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("EN [0]"), "line 1: field outside of a word");
+        assert_eq!(error("word A: u24"), "line 1: A: unknown word size \"u24\"");
+        assert_eq!(error("word A: u8 = 0x1ff"), "A: reset value 0x1ff does not fit in 8-bit register");
+        assert_eq!(error("word A: u8\n B [3:4]"), "line 2: B: invalid bits [3:4]");
+        assert_eq!(error("word A: u8\n B [0] rwx"), "line 2: B: unknown access mode \"rwx\"");
+        assert_eq!(error("word A: u8\n B [1:0] { X = 1, Y = 1 }"), "line 2: B: duplicate value Y");
+        assert_eq!(error("word A: u8\n B [8]"), "A: B: field does not fit in 8-bit register");
+        assert_eq!(error("word A: u64\n B [63:0]"), "A: B: width 64 has no U type");
+        assert_eq!(error("word A: u8\n B [3:0]\n C [4:3]"), "A: fields B and C overlap");
+        assert_eq!(error("word A: u8\n B [0] { X = 2 }"), "A: B: value 0x2 does not fit in U1");
+        assert_eq!(error("word A: u8\n B [0]\n B [1]"), "A: duplicate field B");
+        assert_eq!(error("word A: u8\nword A: u8"), "A: duplicate word");
+    }
+}
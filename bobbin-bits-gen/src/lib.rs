@@ -0,0 +1,127 @@
+//! # bobbin-bits-gen
+//!
+//! Code generators for [bobbin-bits](https://github.com/bobbin-rs/bobbin-bits/) register
+//! definitions.
+//!
+//! ## SVD
+//!
+//! [`svd::parse`] reads a CMSIS-SVD file into a [`model::Device`], and
+//! [`rust::generate`] emits a module per peripheral in which every field is a
+//! `Field<OFFSET, Un, Access>` of the exact U type for its width, every field with
+//! enumerated values gets an enum mapped onto that U type, and arrays of registers and
+//! clusters are indexed with R types.
+//!
+//! ```
+//! let svd = r#"
+//! <device>
+//!   <name>MCU</name>
+//!   <peripherals>
+//!     <peripheral>
+//!       <name>TIM2</name>
+//!       <baseAddress>0x40000000</baseAddress>
+//!       <registers>
+//!         <register>
+//!           <name>CR1</name>
+//!           <addressOffset>0x0</addressOffset>
+//!           <fields>
+//!             <field><name>CEN</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth></field>
+//!             <field><name>CKD</name><bitRange>[9:8]</bitRange></field>
+//!           </fields>
+//!         </register>
+//!       </registers>
+//!     </peripheral>
+//!   </peripherals>
+//! </device>"#;
+//!
+//! let device = bobbin_bits_gen::svd::parse(svd).unwrap();
+//! let code = bobbin_bits_gen::rust::generate(&device);
+//! assert!(code.contains("pub fn cr1() -> Register<cr1::Spec> {"));
+//! assert!(code.contains("pub type CKD = Field<8, U2, RW>;"));
+//! ```
+//!
+//! The generated code requires the `derive` feature of bobbin-bits. The
+//! `bobbin-bits-gen` binary runs the same conversion on a file, and generates Rust or, with
+//! `--c`, a C header from layout files:
+//!
+//! ```text
+//! bobbin-bits-gen STM32F401.svd -o src/stm32f401.rs
+//! bobbin-bits-gen uart.layout -o src/uart.rs
+//! bobbin-bits-gen --c uart.layout -o include/uart.h
+//! ```
+//!
+//! ## Layouts
+//!
+//! [`layout::Layout`] loads a small hand-editable description of words and fields,
+//! validates it and decodes and encodes values at run time without generated code. See
+//! the [`layout`] module for the format.
+//!
+//! The same layout can be turned into Rust with [`rust::generate_layout`] and into a C
+//! header with [`c::generate`], so that C and Rust firmware sharing a layout derive their
+//! masks from one source.
+//!
+//! ## bobbin-bits
+//!
+//! The `bobbin-bits` binary decodes values into fields, encodes field assignments into
+//! values and compares two values field by field, reading the fields from a layout file or
+//! from inline `name:offset:width` specs:
+//!
+//! ```text
+//! $ bobbin-bits decode -f en:0:1 -f mode:1:2 -f presc:4:12 -f busy:31:1 0x4002_1A3C
+//! value = 0x4002_1a3c
+//!
+//!   3322 2222 2222 1111 1111 11
+//!   1098 7654 3210 9876 5432 1098 7654 3210
+//!   0100 0000 0000 0010 0001 1010 0011 1100
+//!   D... .... .... .... CCCC CCCC CCCC .BBA
+//!
+//!   key  field  bits    type  access  bin             hex    dec
+//!   A    en     [0]     U1    rw      0b0             0x0    0
+//!   B    mode   [2:1]   U2    rw      0b10            0x2    2
+//!   C    presc  [15:4]  U12   rw      0b000110100011  0x1a3  419
+//!   D    busy   [31]    U1    rw      0b0             0x0    0
+//!
+//! $ bobbin-bits encode -l ctrl.layout -w CTRL MODE=Alt EN=1 PRESCALE=0x12
+//! $ bobbin-bits diff -l ctrl.layout -w CTRL 0x8000_0005 0x0000_0123
+//! ```
+
+pub mod c;
+mod ident;
+pub mod layout;
+pub mod model;
+pub mod report;
+pub mod rust;
+pub mod svd;
+
+use std::fmt;
+
+/// An error reading a device description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    /// Constructs an error with a message.
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Error { message: message.into() }
+    }
+
+    /// Prefixes the message with the name of the enclosing element.
+    pub fn context(self, name: &str) -> Self {
+        Error { message: format!("{}: {}", name, self.message) }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<roxmltree::Error> for Error {
+    fn from(e: roxmltree::Error) -> Self {
+        Error::new(e.to_string())
+    }
+}
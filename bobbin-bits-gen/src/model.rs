@@ -0,0 +1,245 @@
+//! A device model shared by the parsers and generators.
+
+use bobbin_bits::AccessKind;
+
+use crate::Error;
+
+/// A device: a set of memory-mapped peripherals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Device {
+    pub name: String,
+    pub description: Option<String>,
+    pub peripherals: Vec<Peripheral>,
+}
+
+/// A peripheral instance at a fixed base address.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Peripheral {
+    pub name: String,
+    pub description: Option<String>,
+    pub base_address: u64,
+    pub items: Vec<Item>,
+}
+
+/// A register or a cluster of registers within a peripheral.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Register(Register),
+    Cluster(Cluster),
+}
+
+/// The repetition of a register or cluster as an array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dim {
+    /// The number of elements.
+    pub count: u32,
+    /// The distance in bytes between the start of consecutive elements.
+    pub increment: u64,
+}
+
+/// A group of registers, optionally repeated as an array.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    pub name: String,
+    pub description: Option<String>,
+    pub address_offset: u64,
+    pub dim: Option<Dim>,
+    pub items: Vec<Item>,
+}
+
+/// A register, optionally repeated as an array.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub name: String,
+    pub description: Option<String>,
+    pub address_offset: u64,
+    /// The register width in bits: 8, 16, 32 or 64.
+    pub size: u32,
+    pub reset_value: u64,
+    pub access: AccessKind,
+    pub dim: Option<Dim>,
+    pub fields: Vec<Field>,
+}
+
+/// A bit field within a register.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub description: Option<String>,
+    pub offset: u32,
+    /// The field width in bits, between 1 and 32.
+    pub width: u32,
+    pub access: AccessKind,
+    pub values: Vec<EnumValue>,
+}
+
+/// A named value of a field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumValue {
+    pub name: String,
+    pub description: Option<String>,
+    pub value: u64,
+}
+
+impl Register {
+    /// Returns the field named `name`.
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Checks that the register has a supported size, that every field width maps to a U
+    /// type, that every field and value fits and that no two fields overlap.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !matches!(self.size, 8 | 16 | 32 | 64) {
+            return Err(Error::new(format!("{}: unsupported register size {}", self.name, self.size)));
+        }
+        if self.reset_value.checked_shr(self.size).unwrap_or(0) != 0 {
+            let msg = format!("{}: reset value {:#x} does not fit in {}-bit register", self.name, self.reset_value, self.size);
+            return Err(Error::new(msg));
+        }
+        for (i, f) in self.fields.iter().enumerate() {
+            f.validate(self.size).map_err(|e| e.context(&self.name))?;
+            if let Some(g) = self.fields[..i].iter().find(|g| g.mask() & f.mask() != 0) {
+                return Err(Error::new(format!("{}: fields {} and {} overlap", self.name, g.name, f.name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits `word` into the values of its fields.
+    pub fn decode(&self, word: u64) -> Vec<(&Field, u64)> {
+        self.fields.iter().map(|f| (f, f.extract(word))).collect()
+    }
+
+    /// Returns a copy of `word` with the field named `name` set to `value`.
+    pub fn encode(&self, word: u64, name: &str, value: u64) -> Result<u64, Error> {
+        let f = self.field(name).ok_or_else(|| Error::new(format!("{}: no field {}", self.name, name)))?;
+        if value >> f.width != 0 {
+            return Err(Error::new(format!("{}: value {:#x} does not fit in U{}", f.name, value, f.width)));
+        }
+        Ok(f.insert(word, value))
+    }
+}
+
+impl Field {
+    /// Returns the mask of the field within its register.
+    pub fn mask(&self) -> u64 {
+        (u64::MAX >> (64 - self.width)) << self.offset
+    }
+
+    /// Returns the value of the field in `word`.
+    pub fn extract(&self, word: u64) -> u64 {
+        (word & self.mask()) >> self.offset
+    }
+
+    /// Returns a copy of `word` with the field set to `value`, truncated to the field width.
+    pub fn insert(&self, word: u64, value: u64) -> u64 {
+        (word & !self.mask()) | ((value << self.offset) & self.mask())
+    }
+
+    /// Returns the name of `value`, if it has one.
+    pub fn value_name(&self, value: u64) -> Option<&str> {
+        self.values.iter().find(|v| v.value == value).map(|v| v.name.as_str())
+    }
+
+    /// Parses a value of the field: a value name or a number.
+    pub fn parse_value(&self, text: &str) -> Result<u64, Error> {
+        match self.values.iter().find(|v| v.name == text) {
+            Some(v) => Ok(v.value),
+            None => parse_number(text).map_err(|e| e.context(&self.name)),
+        }
+    }
+
+    fn validate(&self, size: u32) -> Result<(), Error> {
+        if !(1..=32).contains(&self.width) {
+            return Err(Error::new(format!("{}: width {} has no U type", self.name, self.width)));
+        }
+        if self.offset + self.width > size {
+            return Err(Error::new(format!("{}: field does not fit in {}-bit register", self.name, size)));
+        }
+        for v in &self.values {
+            if v.value >> self.width != 0 {
+                return Err(Error::new(format!("{}: value {:#x} does not fit in U{}", self.name, v.value, self.width)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an integer: decimal, `0x` hexadecimal or `0b` binary, with optional `_` separators.
+pub fn parse_number(text: &str) -> Result<u64, Error> {
+    let t = text.trim().replace('_', "");
+    let result = if let Some(hex) = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(bin) = t.strip_prefix("0b").or_else(|| t.strip_prefix("0B")) {
+        u64::from_str_radix(bin, 2)
+    } else {
+        t.parse()
+    };
+    result.map_err(|_| Error::new(format!("invalid number {:?}", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, offset: u32, width: u32) -> Field {
+        Field {
+            name: name.to_string(),
+            description: None,
+            offset,
+            width,
+            access: AccessKind::ReadWrite,
+            values: Vec::new(),
+        }
+    }
+
+    fn register(size: u32, fields: Vec<Field>) -> Register {
+        Register {
+            name: String::from("R"),
+            description: None,
+            address_offset: 0,
+            size,
+            reset_value: 0,
+            access: AccessKind::ReadWrite,
+            dim: None,
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(register(16, vec![field("A", 0, 4), field("B", 4, 12)]).validate().is_ok());
+        let e = register(24, vec![]).validate().unwrap_err();
+        assert_eq!(e.to_string(), "R: unsupported register size 24");
+        let e = register(64, vec![field("A", 0, 33)]).validate().unwrap_err();
+        assert_eq!(e.to_string(), "R: A: width 33 has no U type");
+        let e = register(8, vec![field("A", 4, 5)]).validate().unwrap_err();
+        assert_eq!(e.to_string(), "R: A: field does not fit in 8-bit register");
+        let e = register(8, vec![field("A", 0, 4), field("B", 3, 2)]).validate().unwrap_err();
+        assert_eq!(e.to_string(), "R: fields A and B overlap");
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut mode = field("MODE", 4, 2);
+        mode.values.push(EnumValue { name: String::from("Alt"), description: None, value: 2 });
+        let r = register(8, vec![field("EN", 0, 1), mode]);
+        let decoded: Vec<(&str, u64)> = r.decode(0xa1).iter().map(|(f, v)| (f.name.as_str(), *v)).collect();
+        assert_eq!(decoded, [("EN", 1), ("MODE", 2)]);
+        assert_eq!(r.fields[1].value_name(2), Some("Alt"));
+        assert_eq!(r.fields[1].parse_value("Alt").unwrap(), 2);
+        assert_eq!(r.fields[1].parse_value("0b11").unwrap(), 3);
+        assert_eq!(r.encode(0xff, "MODE", 1).unwrap(), 0xdf);
+        assert_eq!(r.encode(0, "MODE", 4).unwrap_err().to_string(), "MODE: value 0x4 does not fit in U2");
+        assert_eq!(r.encode(0, "BUSY", 1).unwrap_err().to_string(), "R: no field BUSY");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("42").unwrap(), 42);
+        assert_eq!(parse_number("0x4002_1A3C").unwrap(), 0x4002_1a3c);
+        assert_eq!(parse_number("0b1010").unwrap(), 10);
+        assert!(parse_number("0xg").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Text reports of decoded words, as printed by the `bobbin-bits` tool.
+//!
+//! ```
+//! use bobbin_bits_gen::layout::Layout;
+//! use bobbin_bits_gen::report;
+//!
+//! let layout = Layout::parse("
+//!     word CTRL: u16
+//!         EN [0]
+//!         MODE [2:1] { Input = 0, Output = 1, Alt = 2, Analog = 3 }
+//!         PRESCALE [15:8] ro
+//! ").unwrap();
+//!
+//! assert_eq!(report::decode(&layout.words()[0], 0x4a05), "\
+//! CTRL = 0x4a05
+//!
+//!   1111 11
+//!   5432 1098 7654 3210
+//!   0100 1010 0000 0101
+//!   CCCC CCCC .... .BBA
+//!
+//!   key  field     bits    type  access  bin         hex   dec
+//!   A    EN        [0]     U1    rw      0b1         0x1   1
+//!   B    MODE      [2:1]   U2    rw      0b10        0x2   2    Alt
+//!   C    PRESCALE  [15:8]  U8    ro      0b01001010  0x4a  74
+//! ");
+//! ```
+
+use bobbin_bits::AccessKind;
+
+use crate::model::{Field, Register};
+use crate::rust::hex;
+
+/// Returns the key identifying the `i`th field in the ruler.
+fn key(i: usize) -> char {
+    const KEYS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    KEYS.get(i).map_or('*', |&k| k as char)
+}
+
+fn access(access: AccessKind) -> &'static str {
+    match access {
+        AccessKind::ReadOnly => "ro",
+        AccessKind::WriteOnly => "wo",
+        AccessKind::ReadWrite => "rw",
+        AccessKind::WriteOneToClear => "w1c",
+        AccessKind::WriteOneToSet => "w1s",
+        AccessKind::ReadToClear => "rc",
+    }
+}
+
+fn bits(f: &Field) -> String {
+    if f.width == 1 {
+        format!("[{}]", f.offset)
+    } else {
+        format!("[{}:{}]", f.offset + f.width - 1, f.offset)
+    }
+}
+
+/// Formats a row of the ruler, one character per bit from the most significant, in groups of four.
+fn row<F: Fn(u32) -> char>(size: u32, f: F) -> String {
+    let mut out = String::from("  ");
+    for bit in (0..size).rev() {
+        out.push(f(bit));
+        if bit % 4 == 0 && bit != 0 {
+            out.push(' ');
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// Returns an ASCII ruler showing the bit numbers, the bits of each value and the key of
+/// the field that each bit belongs to.
+pub fn ruler(register: &Register, values: &[u64]) -> String {
+    let size = register.size;
+    let mut out = String::new();
+    if size > 10 {
+        out.push_str(&row(size, |bit| if bit >= 10 { char::from(b'0' + (bit / 10) as u8) } else { ' ' }));
+    }
+    out.push_str(&row(size, |bit| char::from(b'0' + (bit % 10) as u8)));
+    for value in values {
+        out.push_str(&row(size, |bit| if value >> bit & 1 != 0 { '1' } else { '0' }));
+    }
+    out.push_str(&row(size, |bit| {
+        match register.fields.iter().position(|f| f.mask() >> bit & 1 != 0) {
+            Some(i) => key(i),
+            None => '.',
+        }
+    }));
+    out
+}
+
+/// Formats rows as left-aligned columns separated by two spaces.
+fn table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| rows.iter().filter_map(|r| r.get(c)).map(|s| s.chars().count()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for r in rows {
+        let mut line = String::from(" ");
+        for (cell, width) in r.iter().zip(&widths) {
+            line.push_str(&format!(" {:1$} ", cell, width));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn value_cells(f: &Field, value: u64) -> Vec<String> {
+    vec![
+        format!("0b{:01$b}", value, f.width as usize),
+        format!("0x{:01$x}", value, (f.width as usize).div_ceil(4)),
+        value.to_string(),
+        f.value_name(value).unwrap_or("").to_string(),
+    ]
+}
+
+fn field_cells(i: usize, f: &Field) -> Vec<String> {
+    vec![key(i).to_string(), f.name.clone(), bits(f), format!("U{}", f.width), access(f.access).to_string()]
+}
+
+/// Returns the value of every field of `value`, with a ruler.
+pub fn decode(register: &Register, value: u64) -> String {
+    let mut out = format!("{} = {}\n\n", register.name, hex(value, register.size as usize / 4));
+    out.push_str(&ruler(register, &[value]));
+    out.push('\n');
+    let mut rows = vec![["key", "field", "bits", "type", "access", "bin", "hex", "dec"].map(String::from).to_vec()];
+    for (i, (f, v)) in register.decode(value).into_iter().enumerate() {
+        let mut row = field_cells(i, f);
+        row.extend(value_cells(f, v));
+        rows.push(row);
+    }
+    out.push_str(&table(&rows));
+    out
+}
+
+/// Returns the fields that differ between `a` and `b`, with a ruler marking the changed bits.
+pub fn diff(register: &Register, a: u64, b: u64) -> String {
+    let digits = register.size as usize / 4;
+    let mut out = format!("{} = {} -> {}\n\n", register.name, hex(a, digits), hex(b, digits));
+    out.push_str(&ruler(register, &[a, b]));
+    out.push_str(&row(register.size, |bit| if (a ^ b) >> bit & 1 != 0 { '^' } else { ' ' }));
+    out.push('\n');
+    let mut rows = vec![["key", "field", "bits", "type", "access", "old", "new"].map(String::from).to_vec()];
+    let format = |f: &Field, v: u64| match f.value_name(v) {
+        Some(name) => format!("{:#x} {}", v, name),
+        None => format!("{:#x}", v),
+    };
+    for (i, f) in register.fields.iter().enumerate() {
+        let (x, y) = (f.extract(a), f.extract(b));
+        if x != y {
+            let mut row = field_cells(i, f);
+            row.push(format(f, x));
+            row.push(format(f, y));
+            rows.push(row);
+        }
+    }
+    let other = (a ^ b) & !register.fields.iter().fold(0, |m, f| m | f.mask());
+    if rows.len() == 1 && other == 0 {
+        out.push_str("  no changes\n");
+        return out;
+    }
+    out.push_str(&table(&rows));
+    if other != 0 {
+        out.push_str(&format!("  other bits changed: {}\n", hex(other, digits)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+
+    fn status() -> Register {
+        Layout::parse(
+            "word STATUS: u8
+                READY [0] ro
+                ERR [3:2] w1c { None = 0, Parity = 1, Framing = 2 }",
+        )
+        .unwrap()
+        .words()[0]
+            .clone()
+    }
+
+    #[test]
+    fn test_ruler() {
+        assert_eq!(ruler(&status(), &[0x85]), "  7654 3210\n  1000 0101\n  .... BB.A\n");
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff(&status(), 0x05, 0x09),
+            "\
+STATUS = 0x05 -> 0x09
+
+  7654 3210
+  0000 0101
+  0000 1001
+  .... BB.A
+       ^^
+
+  key  field  bits   type  access  old         new
+  B    ERR    [3:2]  U2    w1c     0x1 Parity  0x2 Framing
+"
+        );
+        assert!(diff(&status(), 0x05, 0x05).ends_with("  no changes\n"));
+        assert!(diff(&status(), 0x05, 0x45).ends_with("  other bits changed: 0x40\n"));
+    }
+}
@@ -0,0 +1,326 @@
+//! Rust code generation.
+//!
+//! Each peripheral of a device becomes a module containing its base address, an accessor function
+//! returning a `Register` for every register (taking R-typed indices for arrays and
+//! clusters) and a module per register with its `RegisterSpec`, a `Field` alias per
+//! field and a `BitEnum` enum per field with enumerated values. Each word of a layout
+//! becomes a register module on its own.
+//!
+//! The generated code uses `#[derive(BitEnum)]` and so requires the `derive` feature of
+//! bobbin-bits.
+
+use bobbin_bits::AccessKind;
+
+use crate::ident::{camel, snake, upper};
+use crate::layout::Layout;
+use crate::model::{Cluster, Device, Dim, Field, Item, Peripheral, Register};
+
+/// Generates Rust source for a device.
+pub fn generate(device: &Device) -> String {
+    let mut g = Gen::default();
+    g.line(&format!("// Register definitions for {}.", device.name));
+    g.line("// Generated by bobbin-bits-gen. Do not edit.");
+    for p in &device.peripherals {
+        g.blank();
+        peripheral(&mut g, p);
+    }
+    g.out
+}
+
+/// Generates Rust source for a layout, with a module per word.
+pub fn generate_layout(layout: &Layout) -> String {
+    let mut g = Gen::default();
+    g.line("// Generated by bobbin-bits-gen. Do not edit.");
+    for word in layout.words() {
+        g.blank();
+        register(&mut g, word, true);
+    }
+    g.out
+}
+
+/// Allows the upper case field names used by datasheets.
+const ALLOW: &str = "#[allow(non_camel_case_types, clippy::upper_case_acronyms)]";
+
+#[derive(Default)]
+struct Gen {
+    out: String,
+    indent: usize,
+}
+
+impl Gen {
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    fn blank(&mut self) {
+        self.out.push('\n');
+    }
+
+    fn doc(&mut self, doc: Option<&str>) {
+        if let Some(doc) = doc {
+            self.line(&format!("/// {}", doc));
+        }
+    }
+
+    fn open(&mut self, line: &str) {
+        self.line(line);
+        self.indent += 1;
+    }
+
+    fn close(&mut self, line: &str) {
+        self.indent -= 1;
+        self.line(line);
+    }
+}
+
+/// A register or cluster on the path from the peripheral to a register.
+struct Level {
+    /// The path of the module defining the `OFFSET` and `INCREMENT` of the level.
+    module: String,
+    /// The index parameter and dimensions of the level, for arrays.
+    index: Option<(String, Dim)>,
+}
+
+impl Level {
+    fn new(module: String, dim: Option<Dim>, param: &str) -> Self {
+        Level { module, index: dim.map(|dim| (param.to_string(), dim)) }
+    }
+}
+
+/// Returns the type of an array index: an R type if there is one for the array length.
+fn index_type(dim: Dim) -> String {
+    if (1..=32).contains(&dim.count) {
+        format!("R{}", dim.count)
+    } else {
+        String::from("usize")
+    }
+}
+
+fn peripheral(g: &mut Gen, p: &Peripheral) {
+    g.doc(p.description.as_deref());
+    g.line(ALLOW);
+    g.open(&format!("pub mod {} {{", snake(&p.name)));
+    g.line("use bobbin_bits::*;");
+    g.blank();
+    g.line("/// Base address of the peripheral.");
+    g.line(&format!("pub const BASE: usize = {};", hex(p.base_address, 8)));
+    accessors(g, &p.items, "", &mut Vec::new());
+    for item in &p.items {
+        g.blank();
+        self::item(g, item);
+    }
+    g.close("}");
+}
+
+fn accessors(g: &mut Gen, items: &[Item], prefix: &str, levels: &mut Vec<Level>) {
+    let path = levels.last().map_or(String::new(), |l| format!("{}::", l.module));
+    for item in items {
+        match item {
+            Item::Register(r) => {
+                levels.push(Level::new(format!("{}{}", path, snake(&r.name)), r.dim, "index"));
+                accessor(g, &format!("{}{}", prefix, snake(&r.name)), r.description.as_deref(), levels);
+                levels.pop();
+            }
+            Item::Cluster(c) => {
+                let name = snake(&c.name);
+                let name = name.trim_end_matches('_');
+                levels.push(Level::new(format!("{}{}", path, snake(&c.name)), c.dim, &format!("{}_index", name)));
+                accessors(g, &c.items, &format!("{}{}_", prefix, name), levels);
+                levels.pop();
+            }
+        }
+    }
+}
+
+fn accessor(g: &mut Gen, name: &str, doc: Option<&str>, levels: &[Level]) {
+    let indices = levels.iter().filter_map(|l| l.index.as_ref());
+    let params: Vec<String> = indices.clone().map(|(param, dim)| format!("{}: {}", param, index_type(*dim))).collect();
+    g.blank();
+    g.doc(doc);
+    g.line("#[inline]");
+    g.open(&format!(
+        "pub fn {}({}) -> Register<{}::Spec> {{",
+        name,
+        params.join(", "),
+        levels.last().unwrap().module
+    ));
+    for (param, dim) in indices {
+        if index_type(*dim) == "usize" {
+            g.line(&format!("assert!({} < {});", param, dim.count));
+        }
+    }
+    let mut addr = String::from("BASE");
+    for l in levels {
+        addr.push_str(&format!(" + {}::OFFSET", l.module));
+        if let Some((param, dim)) = &l.index {
+            let value = if index_type(*dim) == "usize" { param.clone() } else { format!("{}.value()", param) };
+            addr.push_str(&format!(" + {} * {}::INCREMENT", value, l.module));
+        }
+    }
+    g.line(&format!("unsafe {{ Register::at({}) }}", addr));
+    g.close("}");
+}
+
+fn item(g: &mut Gen, item: &Item) {
+    match item {
+        Item::Register(r) => register(g, r, false),
+        Item::Cluster(c) => cluster(g, c),
+    }
+}
+
+fn offsets(g: &mut Gen, offset: u64, dim: Option<Dim>) {
+    g.line("/// Offset from the start of the enclosing block.");
+    g.line(&format!("pub const OFFSET: usize = {};", hex(offset, 0)));
+    if let Some(dim) = dim {
+        g.line(&format!("/// Distance between the {} array elements.", dim.count));
+        g.line(&format!("pub const INCREMENT: usize = {};", hex(dim.increment, 0)));
+    }
+}
+
+fn cluster(g: &mut Gen, c: &Cluster) {
+    g.doc(c.description.as_deref());
+    g.open(&format!("pub mod {} {{", snake(&c.name)));
+    offsets(g, c.address_offset, c.dim);
+    for item in &c.items {
+        g.blank();
+        self::item(g, item);
+    }
+    g.close("}");
+}
+
+fn register(g: &mut Gen, r: &Register, top_level: bool) {
+    g.doc(r.description.as_deref());
+    if top_level {
+        g.line(ALLOW);
+    }
+    g.open(&format!("pub mod {} {{", snake(&r.name)));
+    g.line("use bobbin_bits::*;");
+    g.blank();
+    offsets(g, r.address_offset, r.dim);
+    g.blank();
+    g.line("/// The register specification.");
+    g.line("pub struct Spec;");
+    g.blank();
+    g.open("impl RegisterSpec for Spec {");
+    g.line(&format!("type Word = u{};", r.size));
+    g.line(&format!("type Fields = {};", field_list(&r.fields)));
+    g.line(&format!("const RESET: u{} = {};", r.size, hex(r.reset_value, r.size as usize / 4)));
+    g.close("}");
+    for f in &r.fields {
+        g.blank();
+        field(g, f);
+    }
+    g.close("}");
+}
+
+/// Returns the `Fields` tuple, nesting tuples of at most 16 fields.
+fn field_list(fields: &[Field]) -> String {
+    let names: Vec<String> = fields.iter().map(|f| upper(&f.name)).collect();
+    let tuple = |names: &[String]| match names.len() {
+        1 => format!("({},)", names[0]),
+        _ => format!("({})", names.join(", ")),
+    };
+    if names.len() <= 16 {
+        tuple(&names)
+    } else {
+        let chunks: Vec<String> = names.chunks(16).map(tuple).collect();
+        tuple(&chunks)
+    }
+}
+
+fn field(g: &mut Gen, f: &Field) {
+    let name = upper(&f.name);
+    g.doc(f.description.as_deref());
+    g.line(&format!("pub type {} = Field<{}, U{}, {}>;", name, f.offset, f.width, access(f.access)));
+    if f.values.is_empty() {
+        return;
+    }
+    let mut enum_name = camel(&f.name);
+    if enum_name == name {
+        enum_name.push_str("Value");
+    }
+    g.blank();
+    g.line(&format!("/// Values of [`{}`].", name));
+    g.line("#[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]");
+    g.line(&format!("#[bits(U{})]", f.width));
+    g.open(&format!("pub enum {} {{", enum_name));
+    let mut seen: Vec<(String, u64)> = Vec::new();
+    for v in &f.values {
+        if seen.iter().any(|(_, value)| *value == v.value) {
+            continue;
+        }
+        let mut variant = camel(&v.name);
+        if seen.iter().any(|(name, _)| *name == variant) {
+            variant = format!("{}{}", variant, v.value);
+        }
+        g.doc(v.description.as_deref());
+        g.line(&format!("{} = {},", variant, v.value));
+        seen.push((variant, v.value));
+    }
+    g.close("}");
+}
+
+fn access(access: AccessKind) -> &'static str {
+    match access {
+        AccessKind::ReadOnly => "RO",
+        AccessKind::WriteOnly => "WO",
+        AccessKind::ReadWrite => "RW",
+        AccessKind::WriteOneToClear => "W1C",
+        AccessKind::WriteOneToSet => "W1S",
+        AccessKind::ReadToClear => "RC",
+    }
+}
+
+/// Formats `value` in hex with at least `digits` digits, grouped in fours.
+pub(crate) fn hex(value: u64, digits: usize) -> String {
+    let digits = format!("{:01$x}", value, digits);
+    if digits.len() <= 4 {
+        return format!("0x{}", digits);
+    }
+    let mut out = String::from("0x");
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 4 == 0 {
+            out.push('_');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(0x14, 0), "0x14");
+        assert_eq!(hex(0, 2), "0x00");
+        assert_eq!(hex(0x4002_0000, 8), "0x4002_0000");
+        assert_eq!(hex(0xa800_0000, 8), "0xa800_0000");
+        assert_eq!(hex(0x12345, 0), "0x1_2345");
+    }
+
+    #[test]
+    fn test_field_list() {
+        let field = |i: usize| Field {
+            name: format!("F{}", i),
+            description: None,
+            offset: i as u32,
+            width: 1,
+            access: AccessKind::ReadWrite,
+            values: Vec::new(),
+        };
+        let fields: Vec<Field> = (0..18).map(field).collect();
+        assert_eq!(field_list(&fields[..0]), "()");
+        assert_eq!(field_list(&fields[..1]), "(F0,)");
+        assert_eq!(field_list(&fields[..2]), "(F0, F1)");
+        assert_eq!(
+            field_list(&fields),
+            "((F0, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15), (F16, F17))"
+        );
+    }
+}
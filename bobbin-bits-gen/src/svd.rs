@@ -0,0 +1,349 @@
+//! CMSIS-SVD parser.
+//!
+//! Supports the subset of SVD used by register definitions: peripherals (including
+//! `derivedFrom`), clusters, `dim` arrays, registers, fields in any of the three bit
+//! position formats and the first `enumeratedValues` set of each field. Register
+//! properties (`size`, `access`, `resetValue`) are inherited from enclosing elements.
+
+use bobbin_bits::AccessKind;
+use roxmltree::{Document, Node};
+
+use crate::model::{parse_number, Cluster, Device, Dim, EnumValue, Field, Item, Peripheral, Register};
+use crate::Error;
+
+/// Parses an SVD document into a device.
+pub fn parse(text: &str) -> Result<Device, Error> {
+    let doc = Document::parse(text)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "device" {
+        return Err(Error::new("expected <device> root element"));
+    }
+    let props = Props::default().inherit(root)?;
+    let name = required(root, "name")?.to_string();
+    let peripherals_node = child(root, "peripherals").ok_or_else(|| Error::new("missing <peripherals>"))?;
+
+    let mut peripherals: Vec<Peripheral> = Vec::new();
+    for node in children(peripherals_node, "peripheral") {
+        let name = required(node, "name")?.to_string();
+        let mut p = match node.attribute("derivedFrom") {
+            Some(base) => {
+                let base = peripherals
+                    .iter()
+                    .find(|p| p.name == base)
+                    .ok_or_else(|| Error::new(format!("{}: unknown base peripheral {}", name, base)))?;
+                Peripheral { name: name.clone(), ..base.clone() }
+            }
+            None => Peripheral { name: name.clone(), description: None, base_address: 0, items: Vec::new() },
+        };
+        if let Some(d) = description(node) {
+            p.description = Some(d);
+        }
+        p.base_address = number(required(node, "baseAddress")?)?;
+        if let Some(registers) = child(node, "registers") {
+            let props = props.inherit(node)?;
+            p.items = items(registers, &props).map_err(|e| e.context(&name))?;
+        }
+        peripherals.push(p);
+    }
+
+    Ok(Device { name, description: description(root), peripherals })
+}
+
+/// Register properties inherited by nested elements.
+#[derive(Clone, Copy)]
+struct Props {
+    size: u32,
+    access: AccessKind,
+    reset_value: u64,
+}
+
+impl Default for Props {
+    fn default() -> Self {
+        Props { size: 32, access: AccessKind::ReadWrite, reset_value: 0 }
+    }
+}
+
+impl Props {
+    fn inherit(&self, node: Node) -> Result<Props, Error> {
+        let mut props = *self;
+        if let Some(size) = text(node, "size") {
+            props.size = number(size)? as u32;
+        }
+        if let Some(access) = text(node, "access") {
+            props.access = access_kind(access, None, None)?;
+        }
+        if let Some(reset) = text(node, "resetValue") {
+            props.reset_value = number(reset)?;
+        }
+        Ok(props)
+    }
+}
+
+fn items(node: Node, props: &Props) -> Result<Vec<Item>, Error> {
+    let mut items = Vec::new();
+    for node in node.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "register" => items.push(Item::Register(register(node, props)?)),
+            "cluster" => items.push(Item::Cluster(cluster(node, props)?)),
+            _ => {}
+        }
+    }
+    Ok(items)
+}
+
+fn cluster(node: Node, props: &Props) -> Result<Cluster, Error> {
+    let name = array_name(required(node, "name")?);
+    let props = props.inherit(node)?;
+    Ok(Cluster {
+        description: description(node),
+        address_offset: number(required(node, "addressOffset")?)?,
+        dim: dim(node)?,
+        items: items(node, &props).map_err(|e| e.context(&name))?,
+        name,
+    })
+}
+
+fn register(node: Node, props: &Props) -> Result<Register, Error> {
+    let name = array_name(required(node, "name")?);
+    let props = props.inherit(node)?;
+    let access = access_kind(
+        access_name(props.access),
+        text(node, "modifiedWriteValues"),
+        text(node, "readAction"),
+    )?;
+    let mut fields = Vec::new();
+    if let Some(fields_node) = child(node, "fields") {
+        for f in children(fields_node, "field") {
+            fields.push(field(f, access).map_err(|e| e.context(&name))?);
+        }
+    }
+    fields.sort_by_key(|f| f.offset);
+    let register = Register {
+        description: description(node),
+        address_offset: number(required(node, "addressOffset")?)?,
+        size: props.size,
+        // An unsupported size leaves the mask empty and is rejected by `validate`.
+        reset_value: props.reset_value & 64u32.checked_sub(props.size).and_then(|n| u64::MAX.checked_shr(n)).unwrap_or(0),
+        access,
+        dim: dim(node)?,
+        fields,
+        name,
+    };
+    register.validate()?;
+    Ok(register)
+}
+
+fn field(node: Node, register_access: AccessKind) -> Result<Field, Error> {
+    let name = required(node, "name")?.to_string();
+    let (offset, width) = if let Some(offset) = text(node, "bitOffset") {
+        let width = text(node, "bitWidth").map_or(Ok(1), number)?;
+        (number(offset)?, width)
+    } else if let (Some(lsb), Some(msb)) = (text(node, "lsb"), text(node, "msb")) {
+        let (lsb, msb) = (number(lsb)?, number(msb)?);
+        (lsb, msb.wrapping_sub(lsb).wrapping_add(1))
+    } else if let Some(range) = text(node, "bitRange") {
+        bit_range(range).ok_or_else(|| Error::new(format!("{}: invalid bitRange {}", name, range)))?
+    } else {
+        return Err(Error::new(format!("{}: missing bit position", name)));
+    };
+    if offset >= 64 || !(1..=64).contains(&width) {
+        return Err(Error::new(format!("{}: invalid bit position", name)));
+    }
+    let access = match text(node, "access") {
+        Some(access) => access_kind(access, text(node, "modifiedWriteValues"), text(node, "readAction"))?,
+        None if text(node, "modifiedWriteValues").is_some() || text(node, "readAction").is_some() => access_kind(
+            access_name(register_access),
+            text(node, "modifiedWriteValues"),
+            text(node, "readAction"),
+        )?,
+        None => register_access,
+    };
+    let values = match child(node, "enumeratedValues") {
+        Some(values) => enum_values(values)?,
+        None => Vec::new(),
+    };
+    Ok(Field {
+        name,
+        description: description(node),
+        offset: offset as u32,
+        width: width as u32,
+        access,
+        values,
+    })
+}
+
+fn enum_values(node: Node) -> Result<Vec<EnumValue>, Error> {
+    let mut values = Vec::new();
+    for node in children(node, "enumeratedValue") {
+        // Default values and values with don't-care bits have no single encoding.
+        let value = match text(node, "value") {
+            Some(v) if !v.contains(['x', 'X']) || v.starts_with("0x") || v.starts_with("0X") => number(v)?,
+            _ => continue,
+        };
+        values.push(EnumValue {
+            name: required(node, "name")?.to_string(),
+            description: description(node),
+            value,
+        });
+    }
+    Ok(values)
+}
+
+fn dim(node: Node) -> Result<Option<Dim>, Error> {
+    match text(node, "dim") {
+        Some(count) => Ok(Some(Dim {
+            count: number(count)? as u32,
+            increment: number(required(node, "dimIncrement")?)?,
+        })),
+        None => Ok(None),
+    }
+}
+
+fn access_kind(access: &str, modified: Option<&str>, read_action: Option<&str>) -> Result<AccessKind, Error> {
+    if read_action == Some("clear") {
+        return Ok(AccessKind::ReadToClear);
+    }
+    match modified {
+        Some("oneToClear") => return Ok(AccessKind::WriteOneToClear),
+        Some("oneToSet") => return Ok(AccessKind::WriteOneToSet),
+        _ => {}
+    }
+    match access {
+        "read-only" => Ok(AccessKind::ReadOnly),
+        "write-only" | "writeOnce" => Ok(AccessKind::WriteOnly),
+        "read-write" | "read-writeOnce" => Ok(AccessKind::ReadWrite),
+        _ => Err(Error::new(format!("unknown access {}", access))),
+    }
+}
+
+fn access_name(access: AccessKind) -> &'static str {
+    match access {
+        AccessKind::ReadOnly | AccessKind::ReadToClear => "read-only",
+        AccessKind::WriteOnly => "write-only",
+        _ => "read-write",
+    }
+}
+
+/// Parses a `[msb:lsb]` bit range into an offset and width.
+fn bit_range(range: &str) -> Option<(u64, u64)> {
+    let range = range.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (msb, lsb) = range.split_once(':')?;
+    let (msb, lsb) = (msb.trim().parse::<u64>().ok()?, lsb.trim().parse::<u64>().ok()?);
+    Some((lsb, msb.checked_sub(lsb)? + 1))
+}
+
+/// Parses an SVD integer: decimal, `0x` hexadecimal, or `0b` / `#` binary.
+fn number(text: &str) -> Result<u64, Error> {
+    match text.trim().strip_prefix('#') {
+        Some(bin) => u64::from_str_radix(bin, 2).map_err(|_| Error::new(format!("invalid number {:?}", text))),
+        None => parse_number(text),
+    }
+}
+
+/// Removes the `%s` placeholder from the name of an array.
+fn array_name(name: &str) -> String {
+    name.replace("[%s]", "").replace("%s", "")
+}
+
+fn description(node: Node) -> Option<String> {
+    let d = text(node, "description")?.split_whitespace().collect::<Vec<_>>().join(" ");
+    if d.is_empty() {
+        None
+    } else {
+        Some(d)
+    }
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn children<'a, 'i: 'a>(node: Node<'a, 'i>, name: &'a str) -> impl Iterator<Item = Node<'a, 'i>> + 'a {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(str::trim)
+}
+
+fn required<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, Error> {
+    text(node, name).ok_or_else(|| Error::new(format!("missing <{}> in <{}>", name, node.tag_name().name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number("42").unwrap(), 42);
+        assert_eq!(number("0x40020000").unwrap(), 0x4002_0000);
+        assert_eq!(number("0XfF").unwrap(), 0xff);
+        assert_eq!(number("#101").unwrap(), 5);
+        assert_eq!(number("0b11").unwrap(), 3);
+        assert!(number("twelve").is_err());
+    }
+
+    #[test]
+    fn test_bit_range() {
+        assert_eq!(bit_range("[7:4]"), Some((4, 4)));
+        assert_eq!(bit_range("[0:0]"), Some((0, 1)));
+        assert_eq!(bit_range("[3:4]"), None);
+        assert_eq!(bit_range("7:4"), None);
+    }
+
+    #[test]
+    fn test_access_kind() {
+        assert_eq!(access_kind("read-only", None, None).unwrap(), AccessKind::ReadOnly);
+        assert_eq!(access_kind("writeOnce", None, None).unwrap(), AccessKind::WriteOnly);
+        assert_eq!(access_kind("read-write", Some("oneToClear"), None).unwrap(), AccessKind::WriteOneToClear);
+        assert_eq!(access_kind("read-only", None, Some("clear")).unwrap(), AccessKind::ReadToClear);
+        assert!(access_kind("sometimes", None, None).is_err());
+    }
+
+    #[test]
+    fn test_array_name() {
+        assert_eq!(array_name("MODER%s"), "MODER");
+        assert_eq!(array_name("CCR[%s]"), "CCR");
+        assert_eq!(array_name("CH%s_CR"), "CH_CR");
+    }
+
+    fn device(fields: &str) -> Result<Device, Error> {
+        sized_device(8, fields)
+    }
+
+    fn sized_device(size: u32, fields: &str) -> Result<Device, Error> {
+        parse(&format!(
+            "<device><name>D</name><peripherals><peripheral><name>P</name>\
+             <baseAddress>0x1000</baseAddress><registers><register><name>R</name>\
+             <addressOffset>0</addressOffset><size>{}</size><fields>{}</fields>\
+             </register></registers></peripheral></peripherals></device>",
+            size, fields
+        ))
+    }
+
+    #[test]
+    fn test_field_errors() {
+        assert!(device("<field><name>A</name><bitRange>[7:0]</bitRange></field>").is_ok());
+        let e = device("<field><name>A</name><bitOffset>4</bitOffset><bitWidth>5</bitWidth></field>").unwrap_err();
+        assert_eq!(e.to_string(), "P: R: A: field does not fit in 8-bit register");
+        let e = device(
+            "<field><name>A</name><lsb>0</lsb><msb>3</msb></field>\
+             <field><name>B</name><bitRange>[5:3]</bitRange></field>",
+        )
+        .unwrap_err();
+        assert_eq!(e.to_string(), "P: R: fields A and B overlap");
+    }
+
+    #[test]
+    fn test_zero_size() {
+        let e = sized_device(0, "").unwrap_err();
+        assert_eq!(e.to_string(), "P: R: unsupported register size 0");
+    }
+
+    #[test]
+    fn test_oversized() {
+        let e = sized_device(128, "").unwrap_err();
+        assert_eq!(e.to_string(), "P: R: unsupported register size 128");
+    }
+}
@@ -0,0 +1,61 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_bobbin-bits"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (output.status.success(), stdout, stderr)
+}
+
+#[test]
+fn test_decode_inline() {
+    let (ok, out, _) = run(&["decode", "-f", "en:0:1", "-f", "mode:1:2", "-f", "busy:31:1", "0x4002_1A3C"]);
+    assert!(ok);
+    assert!(out.starts_with("value = 0x4002_1a3c\n"));
+    assert!(out.contains("  0100 0000 0000 0010 0001 1010 0011 1100\n  C... .... .... .... .... .... .... .BBA\n"));
+    assert!(out.contains("  B    mode   [2:1]  U2    rw      0b10  0x2  2\n"));
+}
+
+#[test]
+fn test_encode() {
+    let layout = "tests/fixtures/example.layout";
+    let (ok, out, _) = run(&["encode", "-l", layout, "-w", "CTRL", "MODE=Alt", "EN=1", "PRESCALE=0x12"]);
+    assert!(ok);
+    assert!(out.starts_with("CTRL = 0x8000_0125\n"));
+    assert!(out.contains("  B    MODE      [2:1]   U2    rw      0b10            0x2    2    Alt\n"));
+
+    let (ok, out, _) = run(&["encode", "-l", layout, "-w", "CTRL", "0", "BUSY=1"]);
+    assert!(ok);
+    assert!(out.starts_with("CTRL = 0x8000_0000\n"));
+
+    let (ok, _, err) = run(&["encode", "-l", layout, "-w", "CTRL", "MODE=4"]);
+    assert!(!ok);
+    assert_eq!(err, "MODE: value 0x4 does not fit in U2\n");
+}
+
+#[test]
+fn test_diff() {
+    let (ok, out, _) = run(&["diff", "-l", "tests/fixtures/example.layout", "-w", "STATUS", "0x0", "0x1"]);
+    assert!(ok);
+    assert!(out.contains("  READY  [0]   U1    ro      0x0  0x1\n"));
+}
+
+#[test]
+fn test_errors() {
+    let (ok, _, err) = run(&["decode", "-l", "tests/fixtures/example.layout", "5"]);
+    assert!(!ok);
+    assert_eq!(err, "the layout has several words: use --word\n");
+    let (ok, _, err) = run(&["decode", "-f", "en:0:1", "-s", "8", "0x100"]);
+    assert!(!ok);
+    assert_eq!(err, "0x100 does not fit in 8-bit word value\n");
+    let (ok, _, err) = run(&["decode", "0x100"]);
+    assert!(!ok);
+    assert_eq!(err, "no fields: use --layout or --field\n");
+    let (ok, _, err) = run(&["decode", "-f", "en:0xffffffffffffffff:1", "1"]);
+    assert!(!ok);
+    assert_eq!(err, "invalid field spec \"en:0xffffffffffffffff:1\", offset plus width overflows\n");
+}
@@ -0,0 +1,56 @@
+/* Generated by bobbin-bits-gen. Do not edit. */
+
+#ifndef EXAMPLE_H
+#define EXAMPLE_H
+
+#include <stdint.h>
+
+/* Control register */
+#define CTRL_OFFSET 0x0U
+#define CTRL_RESET 0x80000000UL
+
+/* Enable */
+#define CTRL_EN_Pos 0U
+#define CTRL_EN_Msk (0x1UL << CTRL_EN_Pos)
+static inline uint8_t ctrl_en_get(uint32_t reg) { return (uint8_t)((reg & CTRL_EN_Msk) >> CTRL_EN_Pos); }
+static inline uint32_t ctrl_en_set(uint32_t reg, uint8_t value) { return (uint32_t)((reg & ~CTRL_EN_Msk) | (((uint32_t)value << CTRL_EN_Pos) & CTRL_EN_Msk)); }
+
+#define CTRL_MODE_Pos 1U
+#define CTRL_MODE_Msk (0x3UL << CTRL_MODE_Pos)
+#define CTRL_MODE_INPUT 0x0U
+#define CTRL_MODE_OUTPUT 0x1U
+#define CTRL_MODE_ALT 0x2U
+#define CTRL_MODE_ANALOG 0x3U
+static inline uint8_t ctrl_mode_get(uint32_t reg) { return (uint8_t)((reg & CTRL_MODE_Msk) >> CTRL_MODE_Pos); }
+static inline uint32_t ctrl_mode_set(uint32_t reg, uint8_t value) { return (uint32_t)((reg & ~CTRL_MODE_Msk) | (((uint32_t)value << CTRL_MODE_Pos) & CTRL_MODE_Msk)); }
+
+#define CTRL_PRESCALE_Pos 4U
+#define CTRL_PRESCALE_Msk (0xfffUL << CTRL_PRESCALE_Pos)
+static inline uint16_t ctrl_prescale_get(uint32_t reg) { return (uint16_t)((reg & CTRL_PRESCALE_Msk) >> CTRL_PRESCALE_Pos); }
+static inline uint32_t ctrl_prescale_set(uint32_t reg, uint16_t value) { return (uint32_t)((reg & ~CTRL_PRESCALE_Msk) | (((uint32_t)value << CTRL_PRESCALE_Pos) & CTRL_PRESCALE_Msk)); }
+
+#define CTRL_BUSY_Pos 31U
+#define CTRL_BUSY_Msk (0x1UL << CTRL_BUSY_Pos)
+static inline uint8_t ctrl_busy_get(uint32_t reg) { return (uint8_t)((reg & CTRL_BUSY_Msk) >> CTRL_BUSY_Pos); }
+
+/* Status register */
+#define STATUS_OFFSET 0x4U
+#define STATUS_RESET 0x0UL
+
+#define STATUS_READY_Pos 0U
+#define STATUS_READY_Msk (0x1UL << STATUS_READY_Pos)
+static inline uint8_t status_ready_get(uint16_t reg) { return (uint8_t)((reg & STATUS_READY_Msk) >> STATUS_READY_Pos); }
+
+#define STATUS_OVR_Pos 1U
+#define STATUS_OVR_Msk (0x1UL << STATUS_OVR_Pos)
+static inline uint8_t status_ovr_get(uint16_t reg) { return (uint8_t)((reg & STATUS_OVR_Msk) >> STATUS_OVR_Pos); }
+static inline uint16_t status_ovr_set(uint16_t reg, uint8_t value) { return (uint16_t)((reg & ~STATUS_OVR_Msk) | (((uint16_t)value << STATUS_OVR_Pos) & STATUS_OVR_Msk)); }
+
+#define STATUS_ERR_Pos 2U
+#define STATUS_ERR_Msk (0x7UL << STATUS_ERR_Pos)
+#define STATUS_ERR_NONE 0x0U
+#define STATUS_ERR_PARITY 0x1U
+#define STATUS_ERR_FRAMING 0x4U
+static inline uint8_t status_err_get(uint16_t reg) { return (uint8_t)((reg & STATUS_ERR_Msk) >> STATUS_ERR_Pos); }
+
+#endif /* EXAMPLE_H */
@@ -0,0 +1,350 @@
+// Register definitions for EXAMPLE.
+// Generated by bobbin-bits-gen. Do not edit.
+
+/// General-purpose I/O
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub mod gpioa {
+    use bobbin_bits::*;
+
+    /// Base address of the peripheral.
+    pub const BASE: usize = 0x4002_0000;
+
+    /// Port mode register
+    #[inline]
+    pub fn moder() -> Register<moder::Spec> {
+        unsafe { Register::at(BASE + moder::OFFSET) }
+    }
+
+    /// Port input data register
+    #[inline]
+    pub fn idr() -> Register<idr::Spec> {
+        unsafe { Register::at(BASE + idr::OFFSET) }
+    }
+
+    /// Port mode register
+    pub mod moder {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x0;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u32;
+            type Fields = (MODER0, MODER1);
+            const RESET: u32 = 0xa800_0000;
+        }
+
+        /// Port 0 mode
+        pub type MODER0 = Field<0, U2, RW>;
+
+        /// Values of [`MODER0`].
+        #[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+        #[bits(U2)]
+        pub enum Moder0 {
+            /// Input mode
+            Input = 0,
+            /// Output mode
+            Output = 1,
+            /// Alternate function mode
+            Alternate = 2,
+            /// Analog mode
+            Analog = 3,
+        }
+
+        /// Port 1 mode
+        pub type MODER1 = Field<2, U2, RW>;
+    }
+
+    /// Port input data register
+    pub mod idr {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x10;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u32;
+            type Fields = ((IDR0, IDR1, IDR2, IDR3, IDR4, IDR5, IDR6, IDR7, IDR8, IDR9, IDR10, IDR11, IDR12, IDR13, IDR14, IDR15), (IDR16,));
+            const RESET: u32 = 0x0000_0000;
+        }
+
+        pub type IDR0 = Field<0, U1, RO>;
+
+        pub type IDR1 = Field<1, U1, RO>;
+
+        pub type IDR2 = Field<2, U1, RO>;
+
+        pub type IDR3 = Field<3, U1, RO>;
+
+        pub type IDR4 = Field<4, U1, RO>;
+
+        pub type IDR5 = Field<5, U1, RO>;
+
+        pub type IDR6 = Field<6, U1, RO>;
+
+        pub type IDR7 = Field<7, U1, RO>;
+
+        pub type IDR8 = Field<8, U1, RO>;
+
+        pub type IDR9 = Field<9, U1, RO>;
+
+        pub type IDR10 = Field<10, U1, RO>;
+
+        pub type IDR11 = Field<11, U1, RO>;
+
+        pub type IDR12 = Field<12, U1, RO>;
+
+        pub type IDR13 = Field<13, U1, RO>;
+
+        pub type IDR14 = Field<14, U1, RO>;
+
+        pub type IDR15 = Field<15, U1, RO>;
+
+        pub type IDR16 = Field<16, U1, RO>;
+    }
+}
+
+/// General-purpose I/O
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub mod gpiob {
+    use bobbin_bits::*;
+
+    /// Base address of the peripheral.
+    pub const BASE: usize = 0x4002_0400;
+
+    /// Port mode register
+    #[inline]
+    pub fn moder() -> Register<moder::Spec> {
+        unsafe { Register::at(BASE + moder::OFFSET) }
+    }
+
+    /// Port input data register
+    #[inline]
+    pub fn idr() -> Register<idr::Spec> {
+        unsafe { Register::at(BASE + idr::OFFSET) }
+    }
+
+    /// Port mode register
+    pub mod moder {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x0;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u32;
+            type Fields = (MODER0, MODER1);
+            const RESET: u32 = 0xa800_0000;
+        }
+
+        /// Port 0 mode
+        pub type MODER0 = Field<0, U2, RW>;
+
+        /// Values of [`MODER0`].
+        #[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+        #[bits(U2)]
+        pub enum Moder0 {
+            /// Input mode
+            Input = 0,
+            /// Output mode
+            Output = 1,
+            /// Alternate function mode
+            Alternate = 2,
+            /// Analog mode
+            Analog = 3,
+        }
+
+        /// Port 1 mode
+        pub type MODER1 = Field<2, U2, RW>;
+    }
+
+    /// Port input data register
+    pub mod idr {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x10;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u32;
+            type Fields = ((IDR0, IDR1, IDR2, IDR3, IDR4, IDR5, IDR6, IDR7, IDR8, IDR9, IDR10, IDR11, IDR12, IDR13, IDR14, IDR15), (IDR16,));
+            const RESET: u32 = 0x0000_0000;
+        }
+
+        pub type IDR0 = Field<0, U1, RO>;
+
+        pub type IDR1 = Field<1, U1, RO>;
+
+        pub type IDR2 = Field<2, U1, RO>;
+
+        pub type IDR3 = Field<3, U1, RO>;
+
+        pub type IDR4 = Field<4, U1, RO>;
+
+        pub type IDR5 = Field<5, U1, RO>;
+
+        pub type IDR6 = Field<6, U1, RO>;
+
+        pub type IDR7 = Field<7, U1, RO>;
+
+        pub type IDR8 = Field<8, U1, RO>;
+
+        pub type IDR9 = Field<9, U1, RO>;
+
+        pub type IDR10 = Field<10, U1, RO>;
+
+        pub type IDR11 = Field<11, U1, RO>;
+
+        pub type IDR12 = Field<12, U1, RO>;
+
+        pub type IDR13 = Field<13, U1, RO>;
+
+        pub type IDR14 = Field<14, U1, RO>;
+
+        pub type IDR15 = Field<15, U1, RO>;
+
+        pub type IDR16 = Field<16, U1, RO>;
+    }
+}
+
+/// General-purpose timer
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub mod tim2 {
+    use bobbin_bits::*;
+
+    /// Base address of the peripheral.
+    pub const BASE: usize = 0x4000_0000;
+
+    /// Status register
+    #[inline]
+    pub fn sr() -> Register<sr::Spec> {
+        unsafe { Register::at(BASE + sr::OFFSET) }
+    }
+
+    /// Prescaler
+    #[inline]
+    pub fn psc() -> Register<psc::Spec> {
+        unsafe { Register::at(BASE + psc::OFFSET) }
+    }
+
+    /// Capture/compare value
+    #[inline]
+    pub fn ch_ccr(ch_index: R4) -> Register<ch::ccr::Spec> {
+        unsafe { Register::at(BASE + ch::OFFSET + ch_index.value() * ch::INCREMENT + ch::ccr::OFFSET) }
+    }
+
+    #[inline]
+    pub fn ch_dly(ch_index: R4, index: R2) -> Register<ch::dly::Spec> {
+        unsafe { Register::at(BASE + ch::OFFSET + ch_index.value() * ch::INCREMENT + ch::dly::OFFSET + index.value() * ch::dly::INCREMENT) }
+    }
+
+    /// Status register
+    pub mod sr {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x10;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u16;
+            type Fields = (UIF, CNT, TYPE);
+            const RESET: u16 = 0x0000;
+        }
+
+        /// Update interrupt flag
+        pub type UIF = Field<0, U1, W1C>;
+
+        /// Event count
+        pub type CNT = Field<8, U3, RC>;
+
+        /// Event type
+        pub type TYPE = Field<12, U2, RO>;
+
+        /// Values of [`TYPE`].
+        #[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+        #[bits(U2)]
+        pub enum Type {
+            None = 0,
+            Overflow = 2,
+        }
+    }
+
+    /// Prescaler
+    pub mod psc {
+        use bobbin_bits::*;
+
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x28;
+
+        /// The register specification.
+        pub struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u8;
+            type Fields = ();
+            const RESET: u8 = 0x01;
+        }
+    }
+
+    /// Capture/compare channel
+    pub mod ch {
+        /// Offset from the start of the enclosing block.
+        pub const OFFSET: usize = 0x40;
+        /// Distance between the 4 array elements.
+        pub const INCREMENT: usize = 0x8;
+
+        /// Capture/compare value
+        pub mod ccr {
+            use bobbin_bits::*;
+
+            /// Offset from the start of the enclosing block.
+            pub const OFFSET: usize = 0x0;
+
+            /// The register specification.
+            pub struct Spec;
+
+            impl RegisterSpec for Spec {
+                type Word = u32;
+                type Fields = (CCR,);
+                const RESET: u32 = 0x0000_0000;
+            }
+
+            pub type CCR = Field<0, U16, RW>;
+        }
+
+        pub mod dly {
+            use bobbin_bits::*;
+
+            /// Offset from the start of the enclosing block.
+            pub const OFFSET: usize = 0x4;
+            /// Distance between the 2 array elements.
+            pub const INCREMENT: usize = 0x2;
+
+            /// The register specification.
+            pub struct Spec;
+
+            impl RegisterSpec for Spec {
+                type Word = u16;
+                type Fields = (DLY,);
+                const RESET: u16 = 0x0000;
+            }
+
+            pub type DLY = Field<0, U12, WO>;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Generated by bobbin-bits-gen. Do not edit.
+
+/// Control register
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub mod ctrl {
+    use bobbin_bits::*;
+
+    /// Offset from the start of the enclosing block.
+    pub const OFFSET: usize = 0x0;
+
+    /// The register specification.
+    pub struct Spec;
+
+    impl RegisterSpec for Spec {
+        type Word = u32;
+        type Fields = (EN, MODE, PRESCALE, BUSY);
+        const RESET: u32 = 0x8000_0000;
+    }
+
+    /// Enable
+    pub type EN = Field<0, U1, RW>;
+
+    pub type MODE = Field<1, U2, RW>;
+
+    /// Values of [`MODE`].
+    #[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+    #[bits(U2)]
+    pub enum Mode {
+        Input = 0,
+        Output = 1,
+        Alt = 2,
+        Analog = 3,
+    }
+
+    pub type PRESCALE = Field<4, U12, RW>;
+
+    pub type BUSY = Field<31, U1, RO>;
+}
+
+/// Status register
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub mod status {
+    use bobbin_bits::*;
+
+    /// Offset from the start of the enclosing block.
+    pub const OFFSET: usize = 0x4;
+
+    /// The register specification.
+    pub struct Spec;
+
+    impl RegisterSpec for Spec {
+        type Word = u16;
+        type Fields = (READY, OVR, ERR);
+        const RESET: u16 = 0x0000;
+    }
+
+    pub type READY = Field<0, U1, RO>;
+
+    pub type OVR = Field<1, U1, W1C>;
+
+    pub type ERR = Field<2, U3, RC>;
+
+    /// Values of [`ERR`].
+    #[derive(BitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+    #[bits(U3)]
+    pub enum Err {
+        None = 0,
+        Parity = 1,
+        Framing = 4,
+    }
+}
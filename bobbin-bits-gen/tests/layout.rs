@@ -0,0 +1,54 @@
+use bobbin_bits::*;
+use bobbin_bits_gen::layout::Layout;
+use bobbin_bits_gen::{c, rust};
+
+#[allow(dead_code)]
+mod example {
+    include!("fixtures/example_layout.rs");
+}
+
+use example::{ctrl, status};
+
+fn layout() -> Layout {
+    Layout::parse(include_str!("fixtures/example.layout")).unwrap()
+}
+
+#[test]
+fn test_generated_matches_fixtures() {
+    assert_eq!(rust::generate_layout(&layout()), include_str!("fixtures/example_layout.rs"));
+    assert_eq!(c::generate(&layout(), "EXAMPLE_H"), include_str!("fixtures/example.h"));
+}
+
+#[test]
+fn test_rust_and_c_agree() {
+    let header = include_str!("fixtures/example.h");
+    let masks = [
+        ("CTRL", "MODE", ctrl::MODE::shift(), ctrl::MODE::mask()),
+        ("CTRL", "PRESCALE", ctrl::PRESCALE::shift(), ctrl::PRESCALE::mask()),
+        ("CTRL", "BUSY", ctrl::BUSY::shift(), ctrl::BUSY::mask()),
+        ("STATUS", "ERR", status::ERR::shift(), status::ERR::mask()),
+    ];
+    for (word, field, shift, mask) in masks {
+        assert!(header.contains(&format!("#define {}_{}_Pos {}U\n", word, field, shift)));
+        assert!(header.contains(&format!(
+            "#define {0}_{1}_Msk (0x{2:x}UL << {0}_{1}_Pos)\n",
+            word,
+            field,
+            mask >> shift
+        )));
+    }
+    assert!(header.contains(&format!("#define STATUS_ERR_FRAMING 0x{:x}U\n", u8::from(U3::from(status::Err::Framing)))));
+    assert!(header.contains(&format!("#define CTRL_RESET 0x{:x}UL\n", <ctrl::Spec as RegisterSpec>::RESET)));
+}
+
+#[test]
+fn test_layout_values() {
+    let layout = layout();
+    let ctrl = layout.word("CTRL").unwrap();
+    let word = ctrl.encode(ctrl.reset_value, "MODE", 2).unwrap();
+    let word = ctrl.encode(word, "PRESCALE", 0x12).unwrap();
+    let typed = <ctrl::Spec as RegisterSpec>::RESET;
+    let typed = ctrl::MODE::write(typed, U2::from(ctrl::Mode::Alt));
+    let typed = ctrl::PRESCALE::write(typed, U12::from(0x12u16));
+    assert_eq!(word, typed as u64);
+}
@@ -0,0 +1,65 @@
+use std::convert::TryFrom;
+
+use bobbin_bits::*;
+use bobbin_bits_gen::{rust, svd};
+
+#[allow(dead_code)]
+mod example {
+    include!("fixtures/example.rs");
+}
+
+use example::{gpioa, gpiob, tim2};
+
+#[test]
+fn test_generated_matches_fixture() {
+    let device = svd::parse(include_str!("fixtures/example.svd")).unwrap();
+    assert_eq!(rust::generate(&device), include_str!("fixtures/example.rs"));
+}
+
+#[test]
+fn test_model() {
+    let device = svd::parse(include_str!("fixtures/example.svd")).unwrap();
+    assert_eq!(device.name, "EXAMPLE");
+    let names: Vec<&str> = device.peripherals.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["GPIOA", "GPIOB", "TIM2"]);
+    assert_eq!(device.peripherals[1].base_address, 0x4002_0400);
+    assert_eq!(device.peripherals[1].items, device.peripherals[0].items);
+}
+
+#[test]
+fn test_addresses() {
+    assert_eq!(gpioa::moder().addr(), 0x4002_0000);
+    assert_eq!(gpiob::idr().addr(), 0x4002_0410);
+    assert_eq!(tim2::psc().addr(), 0x4000_0028);
+    assert_eq!(tim2::ch_ccr(R4::X0).addr(), 0x4000_0040);
+    assert_eq!(tim2::ch_ccr(R4::X3).addr(), 0x4000_0058);
+    assert_eq!(tim2::ch_dly(R4::X2, R2::X1).addr(), 0x4000_0056);
+}
+
+#[test]
+fn test_fields() {
+    use gpioa::moder::{Moder0, MODER1};
+    use tim2::sr::{Type, CNT, TYPE, UIF};
+
+    assert_eq!(<gpioa::moder::Spec as RegisterSpec>::RESET, 0xa800_0000);
+    assert_eq!(MODER1::mask(), 0b1100);
+    assert_eq!(Moder0::from(U2::B10), Moder0::Alternate);
+    assert_eq!(Type::try_from(U2::B10), Ok(Type::Overflow));
+    assert_eq!(Type::try_from(U2::B11), Err(U2::B11));
+
+    // The access policies carry through to the simulated register.
+    let mut bus = SimBus::new();
+    bus.add(
+        SimRegister::new::<tim2::sr::Spec>("SR", 0x10)
+            .field::<UIF>("UIF")
+            .field::<CNT>("CNT")
+            .field::<TYPE>("TYPE"),
+    );
+    bus.poke(0x10, 0x2501);
+    let sr = bus.register::<tim2::sr::Spec>(0x10);
+    assert_eq!(sr.read_field::<CNT>(), U3::B101);
+    assert_eq!(sr.read_field::<CNT>(), U3::B000);
+    sr.write(0x0001);
+    assert_eq!(sr.read_field::<UIF>(), U1::B0);
+    assert_eq!(Type::try_from(sr.read_field::<TYPE>()), Ok(Type::Overflow));
+}
@@ -0,0 +1,102 @@
+/// The access policy of a register field, as a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Read-only: writes have no effect.
+    ReadOnly,
+    /// Write-only: reads return an undefined value.
+    WriteOnly,
+    /// Read-write.
+    ReadWrite,
+    /// Write-one-to-clear: writing a 1 clears the bit, writing a 0 has no effect.
+    WriteOneToClear,
+    /// Write-one-to-set: writing a 1 sets the bit, writing a 0 has no effect.
+    WriteOneToSet,
+    /// Read-to-clear: reading returns the current value and then clears it.
+    ReadToClear,
+}
+
+impl AccessKind {
+    /// Returns true if the field can be read.
+    #[inline]
+    pub const fn is_readable(self) -> bool {
+        !matches!(self, AccessKind::WriteOnly)
+    }
+
+    /// Returns true if the field can be written.
+    #[inline]
+    pub const fn is_writable(self) -> bool {
+        !matches!(self, AccessKind::ReadOnly | AccessKind::ReadToClear)
+    }
+
+    /// Returns true if a value read from the field may be written back unchanged.
+    ///
+    /// This is false for write-one-to-clear and write-one-to-set fields, where writing
+    /// back a set bit would clear or set it as a side effect.
+    #[inline]
+    pub const fn is_write_back(self) -> bool {
+        !matches!(self, AccessKind::WriteOneToClear | AccessKind::WriteOneToSet)
+    }
+}
+
+/// The access policy of a register field, as a type.
+pub trait Access {
+    /// The access policy as a value.
+    const KIND: AccessKind;
+}
+
+/// An access policy for fields that can be read.
+pub trait Readable: Access {}
+
+/// An access policy for fields that can be written.
+pub trait Writable: Access {}
+
+/// Read-only: writes have no effect.
+pub struct RO;
+
+/// Write-only: reads return an undefined value.
+pub struct WO;
+
+/// Read-write.
+pub struct RW;
+
+/// Write-one-to-clear: writing a 1 clears the bit, writing a 0 has no effect.
+pub struct W1C;
+
+/// Write-one-to-set: writing a 1 sets the bit, writing a 0 has no effect.
+pub struct W1S;
+
+/// Read-to-clear: reading returns the current value and then clears it.
+pub struct RC;
+
+impl Access for RO {
+    const KIND: AccessKind = AccessKind::ReadOnly;
+}
+impl Readable for RO {}
+
+impl Access for WO {
+    const KIND: AccessKind = AccessKind::WriteOnly;
+}
+impl Writable for WO {}
+
+impl Access for RW {
+    const KIND: AccessKind = AccessKind::ReadWrite;
+}
+impl Readable for RW {}
+impl Writable for RW {}
+
+impl Access for W1C {
+    const KIND: AccessKind = AccessKind::WriteOneToClear;
+}
+impl Readable for W1C {}
+impl Writable for W1C {}
+
+impl Access for W1S {
+    const KIND: AccessKind = AccessKind::WriteOneToSet;
+}
+impl Readable for W1S {}
+impl Writable for W1S {}
+
+impl Access for RC {
+    const KIND: AccessKind = AccessKind::ReadToClear;
+}
+impl Readable for RC {}
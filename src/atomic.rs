@@ -0,0 +1,338 @@
+#[cfg(target_has_atomic = "16")]
+use core::sync::atomic::AtomicU16;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use bits::{BitWidth, FieldExt};
+#[cfg(target_has_atomic = "8")]
+use {U1, U2, U3, U4, U5, U6, U7};
+#[cfg(target_has_atomic = "16")]
+use {U9, U10, U11, U12, U13, U14, U15};
+use {U17, U18, U19, U20, U21, U22, U23, U24, U25, U26, U27, U28, U29, U30, U31};
+
+/// A u32 of bit fields that can be updated atomically, one field at a time.
+///
+/// Field updates are compare-and-swap loops over the whole word, so concurrent updates
+/// to different fields never lose each other's writes. A single U value can be held in
+/// `AtomicU1` through `AtomicU31` instead; the widths 8, 16 and 32 are covered by the
+/// core atomics.
+///
+/// ```
+/// use bobbin_bits::*;
+/// use std::sync::atomic::Ordering::SeqCst;
+///
+/// // A U3 state, a U12 counter and a U1 flag
+/// static STATE: AtomicFields = AtomicFields::new(0);
+///
+/// STATE.store_field(0, U3::B010, SeqCst);
+/// STATE.store_field(3, U12::from(100u16), SeqCst);
+/// STATE.store_field(15, U1::B1, SeqCst);
+///
+/// let count = STATE.fetch_update_field::<U12, _>(3, SeqCst, SeqCst, |c| Some(U12::from(c.value() + 1)));
+/// assert_eq!(count, Ok(U12::from(100u16)));
+/// assert_eq!(STATE.load_field::<U12>(3, SeqCst), U12::from(101u16));
+/// assert_eq!(STATE.compare_exchange_field(0, U3::B010, U3::B011, SeqCst, SeqCst), Ok(U3::B010));
+/// assert_eq!(STATE.compare_exchange_field(0, U3::B010, U3::B100, SeqCst, SeqCst), Err(U3::B011));
+/// assert_eq!(STATE.load(SeqCst), 0x0000_832b);
+/// ```
+#[derive(Debug, Default)]
+pub struct AtomicFields(AtomicU32);
+
+impl AtomicFields {
+    /// Constructs the word with an initial value.
+    #[inline]
+    pub const fn new(value: u32) -> Self {
+        AtomicFields(AtomicU32::new(value))
+    }
+
+    /// Loads the whole word.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> u32 {
+        self.0.load(order)
+    }
+
+    /// Stores the whole word.
+    #[inline]
+    pub fn store(&self, value: u32, order: Ordering) {
+        self.0.store(value, order)
+    }
+
+    /// Consumes the atomic and returns the word.
+    #[inline]
+    pub fn into_inner(self) -> u32 {
+        self.0.into_inner()
+    }
+
+    /// Loads the field of type `T` starting at bit `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    pub fn load_field<T: BitWidth>(&self, offset: u32, order: Ordering) -> T {
+        self.0.load(order).get_field(offset)
+    }
+
+    /// Stores `value` into the field of type `T` starting at bit `offset`, leaving the
+    /// other fields unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    pub fn store_field<T: BitWidth>(&self, offset: u32, value: T, order: Ordering) {
+        let _ = self.fetch_update_field::<T, _>(offset, order, load_order(order), |_| Some(value));
+    }
+
+    /// Replaces the field of type `T` starting at bit `offset` with the value returned by
+    /// `f`, retrying if another thread changes the word in the meantime.
+    ///
+    /// Returns the previous value of the field, or `Err` with the current value if `f`
+    /// returns `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    pub fn fetch_update_field<T, F>(
+        &self,
+        offset: u32,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        mut f: F,
+    ) -> Result<T, T>
+    where
+        T: BitWidth,
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut word = self.0.load(fetch_order);
+        loop {
+            let current = word.get_field::<T>(offset);
+            let new = match f(current) {
+                Some(new) => word.with_field(offset, new),
+                None => return Err(current),
+            };
+            match self.0.compare_exchange_weak(word, new, set_order, fetch_order) {
+                Ok(_) => return Ok(current),
+                Err(actual) => word = actual,
+            }
+        }
+    }
+
+    /// Stores `new` into the field of type `T` starting at bit `offset` if the field
+    /// currently holds `current`, regardless of the other fields.
+    ///
+    /// Returns the previous value of the field, which is `current` on success.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    pub fn compare_exchange_field<T>(
+        &self,
+        offset: u32,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T>
+    where
+        T: BitWidth,
+    {
+        let mut word = self.0.load(failure);
+        loop {
+            let value = word.get_field::<T>(offset);
+            if value.bits() != current.bits() {
+                return Err(value);
+            }
+            match self.0.compare_exchange_weak(word, word.with_field(offset, new), success, failure) {
+                Ok(_) => return Ok(value),
+                Err(actual) => word = actual,
+            }
+        }
+    }
+}
+
+/// Returns the strongest ordering allowed for the load half of an update with `order`.
+#[inline]
+fn load_order(order: Ordering) -> Ordering {
+    match order {
+        Ordering::Release => Ordering::Relaxed,
+        Ordering::AcqRel => Ordering::Acquire,
+        order => order,
+    }
+}
+
+macro_rules! impl_atomic {
+    (#[$cfg:meta] $($id:ident, $t:ident, $atomic:ident, $prim:ty;)*) => ($(
+        #[doc = concat!("A `", stringify!($t), "` that can be shared between threads.")]
+        #[$cfg]
+        #[derive(Debug, Default)]
+        pub struct $id($atomic);
+
+        #[$cfg]
+        impl $id {
+            /// Constructs the atomic with an initial value.
+            #[inline]
+            pub const fn new(value: $t) -> Self {
+                $id($atomic::new(value.value() as $prim))
+            }
+
+            /// Loads the value.
+            #[inline]
+            pub fn load(&self, order: Ordering) -> $t {
+                $t::from_bits_truncate(self.0.load(order) as u32)
+            }
+
+            /// Stores a value.
+            #[inline]
+            pub fn store(&self, value: $t, order: Ordering) {
+                self.0.store(value.bits() as $prim, order)
+            }
+
+            /// Stores a value, returning the previous value.
+            #[inline]
+            pub fn swap(&self, value: $t, order: Ordering) -> $t {
+                $t::from_bits_truncate(self.0.swap(value.bits() as $prim, order) as u32)
+            }
+
+            /// Stores `new` if the current value is `current`, returning the previous value.
+            #[inline]
+            pub fn compare_exchange(
+                &self,
+                current: $t,
+                new: $t,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<$t, $t> {
+                self.0
+                    .compare_exchange(current.bits() as $prim, new.bits() as $prim, success, failure)
+                    .map(|v| $t::from_bits_truncate(v as u32))
+                    .map_err(|v| $t::from_bits_truncate(v as u32))
+            }
+
+            /// Replaces the value with the value returned by `f`, retrying if another thread
+            /// changes it in the meantime.
+            #[inline]
+            pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, mut f: F) -> Result<$t, $t>
+            where
+                F: FnMut($t) -> Option<$t>,
+            {
+                self.0
+                    .fetch_update(set_order, fetch_order, |v| f($t::from_bits_truncate(v as u32)).map(|v| v.bits() as $prim))
+                    .map(|v| $t::from_bits_truncate(v as u32))
+                    .map_err(|v| $t::from_bits_truncate(v as u32))
+            }
+
+            /// Consumes the atomic and returns the value.
+            #[inline]
+            pub fn into_inner(self) -> $t {
+                $t::from_bits_truncate(self.0.into_inner() as u32)
+            }
+        }
+    )*)
+}
+
+impl_atomic! {
+    #[cfg(target_has_atomic = "8")]
+    AtomicU1, U1, AtomicU8, u8;
+    AtomicU2, U2, AtomicU8, u8;
+    AtomicU3, U3, AtomicU8, u8;
+    AtomicU4, U4, AtomicU8, u8;
+    AtomicU5, U5, AtomicU8, u8;
+    AtomicU6, U6, AtomicU8, u8;
+    AtomicU7, U7, AtomicU8, u8;
+}
+
+impl_atomic! {
+    #[cfg(target_has_atomic = "16")]
+    AtomicU9, U9, AtomicU16, u16;
+    AtomicU10, U10, AtomicU16, u16;
+    AtomicU11, U11, AtomicU16, u16;
+    AtomicU12, U12, AtomicU16, u16;
+    AtomicU13, U13, AtomicU16, u16;
+    AtomicU14, U14, AtomicU16, u16;
+    AtomicU15, U15, AtomicU16, u16;
+}
+
+impl_atomic! {
+    #[cfg(target_has_atomic = "32")]
+    AtomicU17, U17, AtomicU32, u32;
+    AtomicU18, U18, AtomicU32, u32;
+    AtomicU19, U19, AtomicU32, u32;
+    AtomicU20, U20, AtomicU32, u32;
+    AtomicU21, U21, AtomicU32, u32;
+    AtomicU22, U22, AtomicU32, u32;
+    AtomicU23, U23, AtomicU32, u32;
+    AtomicU24, U24, AtomicU32, u32;
+    AtomicU25, U25, AtomicU32, u32;
+    AtomicU26, U26, AtomicU32, u32;
+    AtomicU27, U27, AtomicU32, u32;
+    AtomicU28, U28, AtomicU32, u32;
+    AtomicU29, U29, AtomicU32, u32;
+    AtomicU30, U30, AtomicU32, u32;
+    AtomicU31, U31, AtomicU32, u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::Ordering::SeqCst;
+    use std::sync::Arc;
+    use std::thread;
+    use U8;
+
+    #[test]
+    fn test_atomic_fields() {
+        let a = AtomicFields::new(0xffff_0000);
+        a.store_field(4, U8::from(0xa5u8), SeqCst);
+        assert_eq!(a.load(SeqCst), 0xffff_0a50);
+        assert_eq!(a.load_field::<U4>(28, SeqCst), U4::B1111);
+        assert_eq!(a.fetch_update_field::<U4, _>(28, SeqCst, SeqCst, |_| None), Err(U4::B1111));
+        assert_eq!(a.into_inner(), 0xffff_0a50);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_atomic_fields_overflow() {
+        AtomicFields::new(0).load_field::<U4>(30, SeqCst);
+    }
+
+    #[test]
+    fn test_atomic_fields_concurrent() {
+        // Each thread increments its own U8 counter; no increment may be lost.
+        let a = Arc::new(AtomicFields::default());
+        let threads: std::vec::Vec<_> = (0..4)
+            .map(|i| {
+                let a = a.clone();
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        a.fetch_update_field::<U8, _>(i * 8, SeqCst, SeqCst, |c| Some(U8::from(c.value() + 1)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(a.load(SeqCst), 0xc8c8_c8c8);
+    }
+
+    #[test]
+    fn test_atomic_u() {
+        static FLAG: AtomicU1 = AtomicU1::new(U1::B0);
+        assert_eq!(FLAG.swap(U1::B1, SeqCst), U1::B0);
+        assert_eq!(FLAG.load(SeqCst), U1::B1);
+
+        let a = AtomicU12::new(U12::from(0xfffu16));
+        assert_eq!(a.compare_exchange(U12::from(0u16), U12::from(1u16), SeqCst, SeqCst), Err(U12::from(0xfffu16)));
+        assert_eq!(a.fetch_update(SeqCst, SeqCst, |v| Some(U12::from(v.value() - 1))), Ok(U12::from(0xfffu16)));
+        a.store(U12::from(0x123u16), SeqCst);
+        assert_eq!(a.into_inner(), U12::from(0x123u16));
+
+        let a = AtomicU20::default();
+        assert_eq!(a.load(SeqCst), U20::from(0u32));
+    }
+}
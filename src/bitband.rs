@@ -0,0 +1,309 @@
+use bits::Word;
+use register::{Bus, Mmio};
+use {R32, U1};
+
+/// A bit-band region: the first 1MB of SRAM or of the peripheral space, and its alias.
+struct Region {
+    base: usize,
+    alias: usize,
+}
+
+const REGION_SIZE: usize = 0x10_0000;
+
+const REGIONS: [Region; 2] = [
+    Region { base: 0x2000_0000, alias: 0x2200_0000 },
+    Region { base: 0x4000_0000, alias: 0x4200_0000 },
+];
+
+/// Returns the bit-band alias address of bit `bit` of the word at `addr`, or `None` if
+/// `addr` is not within a Cortex-M bit-band region.
+///
+/// The regions are the first 1MB of SRAM at 0x2000_0000, aliased at 0x2200_0000, and the
+/// first 1MB of the peripheral space at 0x4000_0000, aliased at 0x4200_0000. Each bit is
+/// aliased by a word, so that the alias of bit `n` of byte `b` is
+/// `alias + (b - base) * 32 + n * 4`.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// assert_eq!(try_bit_band_alias(0x4001_0010, R32::X05), Some(0x4220_0214));
+/// assert_eq!(try_bit_band_alias(0x2000_0000, R32::X1f), Some(0x2200_007c));
+/// assert_eq!(try_bit_band_alias(0x0800_0000, R32::X00), None);
+/// ```
+#[inline]
+pub const fn try_bit_band_alias(addr: usize, bit: R32) -> Option<usize> {
+    let mut i = 0;
+    while i < REGIONS.len() {
+        let r = &REGIONS[i];
+        // The word containing the bit must lie entirely within the region.
+        if addr >= r.base && addr - r.base <= REGION_SIZE - 4 {
+            return Some(r.alias + (addr - r.base) * 32 + bit.value() * 4);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns the bit-band alias address of bit `bit` of the word at `addr`.
+///
+/// # Panics
+///
+/// Panics if `addr` is not within a bit-band region.
+#[inline]
+pub const fn bit_band_alias(addr: usize, bit: R32) -> usize {
+    match try_bit_band_alias(addr, bit) {
+        Some(alias) => alias,
+        None => panic!("address is not in a bit-band region"),
+    }
+}
+
+/// Returns the address of the aligned word and the bit within it that are aliased by the
+/// bit-band alias address `alias`, or `None` if `alias` is not within an alias region.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// assert_eq!(bit_band_target(0x4220_0214), Some((0x4001_0010, R32::X05)));
+/// assert_eq!(bit_band_target(0x4220_0234), Some((0x4001_0010, R32::X0d)));
+/// assert_eq!(bit_band_target(0x4001_0010), None);
+/// ```
+#[inline]
+pub fn bit_band_target(alias: usize) -> Option<(usize, R32)> {
+    REGIONS.iter().find(|r| alias >= r.alias && alias - r.alias < REGION_SIZE * 32).map(|r| {
+        let offset = (alias - r.alias) / 4;
+        let byte = r.base + offset / 8;
+        let bit = (byte & 3) * 8 + offset % 8;
+        (byte & !3, R32::from(bit as u32))
+    })
+}
+
+/// A single bit accessed through its bit-band alias, reading and writing `U1` values.
+///
+/// Each read or write is a single access to the alias word, which the processor turns
+/// into an atomic read-modify-write of the bit. Bit-banding is only available on targets
+/// with 32- or 64-bit pointers.
+///
+/// ```
+/// use bobbin_bits::*;
+/// use std::cell::Cell;
+///
+/// // A bus with a single word at 0x4001_0010, with bit-band accesses emulated on the host.
+/// struct Mem(Cell<u32>);
+///
+/// unsafe impl Bus for &Mem {
+///     unsafe fn read<W: Word>(&self, addr: usize) -> W {
+///         assert_eq!(addr, 0x4001_0010);
+///         W::from_u64_truncate(self.0.get() as u64)
+///     }
+///
+///     unsafe fn write<W: Word>(&self, addr: usize, value: W) {
+///         assert_eq!(addr, 0x4001_0010);
+///         self.0.set(value.to_u64() as u32)
+///     }
+/// }
+///
+/// let mem = Mem(Cell::new(0));
+/// let bus = BitBandBus::new(&mem);
+/// let en = unsafe { BitBandBit::with_bus(bus, 0x4001_0010, R32::X05) };
+/// assert_eq!(en.alias(), 0x4220_0214);
+///
+/// en.write(U1::B1);
+/// assert_eq!(mem.0.get(), 0x0000_0020);
+/// assert_eq!(en.read(), U1::B1);
+/// en.clear();
+/// assert_eq!(mem.0.get(), 0x0000_0000);
+/// ```
+pub struct BitBandBit<B: Bus = Mmio> {
+    bus: B,
+    alias: usize,
+}
+
+impl BitBandBit {
+    /// Constructs a memory-mapped bit-band bit for bit `bit` of the word at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// The alias of the bit must be valid for volatile reads and writes for as long as
+    /// the bit is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `addr` is not within a bit-band region.
+    #[inline]
+    pub const unsafe fn at(addr: usize, bit: R32) -> Self {
+        BitBandBit::with_bus(Mmio::BUS, addr, bit)
+    }
+}
+
+impl<B: Bus> BitBandBit<B> {
+    /// Constructs a bit-band bit for bit `bit` of the word at `addr`, accessed on `bus`.
+    ///
+    /// # Safety
+    ///
+    /// The alias of the bit must be valid for reads and writes of a u32 on `bus` for as
+    /// long as the bit is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `addr` is not within a bit-band region.
+    #[inline]
+    pub const unsafe fn with_bus(bus: B, addr: usize, bit: R32) -> Self {
+        BitBandBit { bus, alias: bit_band_alias(addr, bit) }
+    }
+
+    /// Returns the alias address of the bit.
+    #[inline]
+    pub fn alias(&self) -> usize {
+        self.alias
+    }
+
+    /// Reads the bit.
+    #[inline]
+    pub fn read(&self) -> U1 {
+        U1::from(unsafe { self.bus.read::<u32>(self.alias) } & 1 != 0)
+    }
+
+    /// Writes the bit.
+    #[inline]
+    pub fn write(&self, value: U1) {
+        unsafe { self.bus.write::<u32>(self.alias, value.value() as u32) }
+    }
+
+    /// Sets the bit.
+    #[inline]
+    pub fn set(&self) {
+        self.write(U1::B1)
+    }
+
+    /// Clears the bit.
+    #[inline]
+    pub fn clear(&self) {
+        self.write(U1::B0)
+    }
+}
+
+/// A bus that emulates bit-band aliases on top of another bus.
+///
+/// Accesses to an alias region are turned into a read of the target u32 word, or a
+/// read-modify-write of it, on the inner bus; all other accesses are passed through. This
+/// allows code using `BitBandBit` to be tested on the host against a simulated bus.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BitBandBus<B: Bus> {
+    inner: B,
+}
+
+impl<B: Bus> BitBandBus<B> {
+    /// Wraps `inner`.
+    #[inline]
+    pub const fn new(inner: B) -> Self {
+        BitBandBus { inner }
+    }
+
+    /// Returns the inner bus.
+    #[inline]
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+// An alias address is valid if its target word is valid on the inner bus, and any other
+// address if it is valid on the inner bus.
+unsafe impl<B: Bus> Bus for BitBandBus<B> {
+    unsafe fn read<W: Word>(&self, addr: usize) -> W {
+        match bit_band_target(addr) {
+            Some((word, bit)) => W::from_u64_truncate((self.inner.read::<u32>(word) >> bit.value() & 1) as u64),
+            None => self.inner.read(addr),
+        }
+    }
+
+    unsafe fn write<W: Word>(&self, addr: usize, value: W) {
+        match bit_band_target(addr) {
+            Some((word, bit)) => {
+                let mask = 1u32 << bit.value();
+                let old = self.inner.read::<u32>(word);
+                let new = if value.to_u64() & 1 != 0 { old | mask } else { old & !mask };
+                self.inner.write::<u32>(word, new)
+            }
+            None => self.inner.write(addr, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    /// Four words of SRAM at 0x2000_0000.
+    struct Sram(Cell<[u32; 4]>);
+
+    unsafe impl Bus for &Sram {
+        unsafe fn read<W: Word>(&self, addr: usize) -> W {
+            W::from_u64_truncate(self.0.get()[(addr - 0x2000_0000) / 4] as u64)
+        }
+
+        unsafe fn write<W: Word>(&self, addr: usize, value: W) {
+            let mut words = self.0.get();
+            words[(addr - 0x2000_0000) / 4] = value.to_u64() as u32;
+            self.0.set(words)
+        }
+    }
+
+    #[test]
+    fn test_alias() {
+        assert_eq!(bit_band_alias(0x2000_0000, R32::X00), 0x2200_0000);
+        assert_eq!(bit_band_alias(0x200f_fffc, R32::X1f), 0x23ff_fffc);
+        assert_eq!(bit_band_alias(0x4000_0004, R32::X01), 0x4200_0084);
+        assert_eq!(try_bit_band_alias(0x200f_fffd, R32::X00), None);
+        assert_eq!(try_bit_band_alias(0x2010_0000, R32::X00), None);
+        assert_eq!(try_bit_band_alias(0x1fff_fffc, R32::X00), None);
+
+        // A word address plus bit index is the same as the byte address plus bit index.
+        assert_eq!(bit_band_alias(0x2000_0004, R32::X09), bit_band_alias(0x2000_0005, R32::X01));
+    }
+
+    #[test]
+    fn test_target_roundtrip() {
+        for &addr in &[0x2000_0000, 0x2000_1234 & !3, 0x200f_fffc, 0x4000_0000, 0x4002_1a3c] {
+            for bit in 0..32u32 {
+                let bit = R32::from(bit);
+                assert_eq!(bit_band_target(bit_band_alias(addr, bit)), Some((addr, bit)));
+            }
+        }
+        assert_eq!(bit_band_target(0x2400_0000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "address is not in a bit-band region")]
+    fn test_alias_outside_region() {
+        bit_band_alias(0x6000_0000, R32::X00);
+    }
+
+    #[test]
+    fn test_bit_band_bus() {
+        let sram = Sram(Cell::new([0; 4]));
+        let bus = BitBandBus::new(&sram);
+        // The Sram bus holds the target words of these aliases.
+        let bits: [BitBandBit<_>; 3] = unsafe {
+            [
+                BitBandBit::with_bus(bus, 0x2000_0004, R32::X00),
+                BitBandBit::with_bus(bus, 0x2000_0004, R32::X1f),
+                BitBandBit::with_bus(bus, 0x2000_000c, R32::X10),
+            ]
+        };
+        for b in &bits {
+            b.set();
+        }
+        assert_eq!(sram.0.get(), [0, 0x8000_0001, 0, 0x0001_0000]);
+        bits[0].clear();
+        assert_eq!(bits[0].read(), U1::B0);
+        assert_eq!(bits[1].read(), U1::B1);
+        assert_eq!(sram.0.get(), [0, 0x8000_0000, 0, 0x0001_0000]);
+
+        // Accesses outside of the alias regions pass through.
+        unsafe {
+            bus.write::<u32>(0x2000_0000, 0x1234_5678);
+            assert_eq!(bus.read::<u32>(0x2000_0000), 0x1234_5678);
+        }
+    }
+}
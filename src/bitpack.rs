@@ -0,0 +1,244 @@
+//! Frame-of-reference bit packing for blocks of integers.
+//!
+//! A block of u32 values is reduced to residuals against a reference value, either the
+//! minimum of the block or, for slowly changing series, the previous value. The residuals
+//! are then packed into u64 words in the smallest width that holds all of them, with
+//! value `i` at bits `i * width` onwards. The width is reported as an `R33`, since a block
+//! of identical values needs no bits at all and an arbitrary block needs 32.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits::bitpack::*;
+//!
+//! let samples = [1000, 1003, 1001, 1010, 1002, 1007];
+//! let mut words = [0u64; 6];
+//!
+//! let (header, n) = encode(&samples, Mode::FrameOfReference, &mut words);
+//! assert_eq!(header.reference, 1000);
+//! assert_eq!(header.width, R33::X04);
+//! assert_eq!(n, 1);
+//!
+//! let mut out = [0u32; 6];
+//! header.unpack(&words[..n], &mut out);
+//! assert_eq!(out, samples);
+//!
+//! // Deltas of a monotonic counter pack into fewer bits.
+//! let counter = [5_000_000, 5_000_010, 5_000_020, 5_000_031, 5_000_041];
+//! let (header, n) = encode(&counter, Mode::Delta, &mut words);
+//! assert_eq!(header.width, R33::X05);
+//! header.unpack(&words[..n], &mut out[..5]);
+//! assert_eq!(out[..5], counter);
+//! ```
+
+use codes::{zigzag_decode, zigzag_encode};
+use packed::{packed_words, read_bits};
+use R33;
+
+/// How the residuals of a block are computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Each value minus the minimum of the block.
+    FrameOfReference,
+    /// Each value minus the previous value, zigzag encoded, with the first value as the
+    /// reference. Differences wrap, so any block can be encoded.
+    Delta,
+}
+
+/// The parameters needed to decode a packed block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// How the residuals were computed.
+    pub mode: Mode,
+    /// The minimum of the block, or its first value in delta mode.
+    pub reference: u32,
+    /// The width in bits of each residual.
+    pub width: R33,
+}
+
+/// Returns the number of bits needed to hold `value`, as an `R33`.
+///
+/// ```
+/// use bobbin_bits::*;
+/// use bobbin_bits::bitpack::bit_width;
+///
+/// assert_eq!(bit_width(0), R33::X00);
+/// assert_eq!(bit_width(0x80), R33::X08);
+/// assert_eq!(bit_width(u32::MAX), R33::X20);
+/// ```
+#[inline]
+pub fn bit_width(value: u32) -> R33 {
+    R33::from(32 - value.leading_zeros())
+}
+
+/// Calls `f` with the residual of each value of `values` in `mode`, given `reference`.
+#[inline]
+fn residuals<F: FnMut(u32)>(values: &[u32], mode: Mode, reference: u32, mut f: F) {
+    match mode {
+        Mode::FrameOfReference => values.iter().for_each(|&v| f(v.wrapping_sub(reference))),
+        Mode::Delta => {
+            let mut prev = reference;
+            for &v in values {
+                f(zigzag_encode(v.wrapping_sub(prev) as i32));
+                prev = v;
+            }
+        }
+    }
+}
+
+impl BlockHeader {
+    /// Chooses the reference and the smallest width that can encode `values` in `mode`.
+    pub fn analyze(values: &[u32], mode: Mode) -> Self {
+        let reference = match mode {
+            Mode::FrameOfReference => values.iter().copied().min().unwrap_or(0),
+            Mode::Delta => values.first().copied().unwrap_or(0),
+        };
+        let mut bits = 0;
+        residuals(values, mode, reference, |r| bits |= r);
+        BlockHeader { mode, reference, width: bit_width(bits) }
+    }
+
+    /// Returns the number of words needed to pack `len` values.
+    #[inline]
+    pub fn words(&self, len: usize) -> usize {
+        packed_words(self.width.value() as u32, len)
+    }
+
+    /// Packs the residuals of `values` into `out`, returning the number of words written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` has fewer than `self.words(values.len())` words, or if a residual
+    /// does not fit in `self.width`.
+    pub fn pack(&self, values: &[u32], out: &mut [u64]) -> usize {
+        let width = self.width.value() as u32;
+        let words = self.words(values.len());
+        assert!(out.len() >= words, "output buffer is too small");
+        let (mut acc, mut bits, mut k) = (0u64, 0, 0);
+        residuals(values, self.mode, self.reference, |r| {
+            assert!((r as u64) >> width == 0, "value does not fit in block width");
+            acc |= (r as u64) << bits;
+            bits += width;
+            if bits >= 64 {
+                out[k] = acc;
+                k += 1;
+                bits -= 64;
+                // Carry the bits of `r` that did not fit in the full word.
+                acc = if bits == 0 { 0 } else { r as u64 >> (width - bits) };
+            }
+        });
+        if bits > 0 {
+            out[k] = acc;
+        }
+        words
+    }
+
+    /// Decodes `out.len()` values from `packed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `packed` has fewer than `self.words(out.len())` words.
+    pub fn unpack(&self, packed: &[u64], out: &mut [u32]) {
+        let width = self.width.value() as u32;
+        assert!(packed.len() >= self.words(out.len()), "packed block is too short");
+        let mut prev = self.reference;
+        for (i, o) in out.iter_mut().enumerate() {
+            let r = read_bits(packed, i * width as usize, width) as u32;
+            *o = match self.mode {
+                Mode::FrameOfReference => self.reference.wrapping_add(r),
+                Mode::Delta => {
+                    prev = prev.wrapping_add(zigzag_decode(r) as u32);
+                    prev
+                }
+            };
+        }
+    }
+}
+
+/// Analyzes and packs `values` in `mode`, returning the header and the number of words
+/// written to `out`.
+///
+/// # Panics
+///
+/// Panics if `out` is too small for the chosen width. `packed_words(32, values.len())`
+/// words are always enough.
+#[inline]
+pub fn encode(values: &[u32], mode: Mode, out: &mut [u64]) -> (BlockHeader, usize) {
+    let header = BlockHeader::analyze(values, mode);
+    let n = header.pack(values, out);
+    (header, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(values: &[u32], mode: Mode) -> BlockHeader {
+        let mut words = [0u64; 64];
+        let (header, n) = encode(values, mode, &mut words);
+        assert_eq!(n, header.words(values.len()));
+        let mut out = [0u32; 128];
+        header.unpack(&words[..n], &mut out[..values.len()]);
+        assert_eq!(out[..values.len()], *values);
+        header
+    }
+
+    #[test]
+    fn test_widths() {
+        let mut values = [0u32; 100];
+        for w in 0..=32u32 {
+            let (base, mask) = (if w < 32 { 7 } else { 0 }, ((1u64 << w) - 1) as u32);
+            for (i, v) in values.iter_mut().enumerate() {
+                *v = base + ((i as u32).wrapping_mul(0x9e37_79b9) & mask);
+            }
+            values[0] = base + mask;
+            values[1] = base;
+            let header = round_trip(&values, Mode::FrameOfReference);
+            assert_eq!(header.width.value(), w as usize);
+            round_trip(&values, Mode::Delta);
+        }
+    }
+
+    #[test]
+    fn test_constant() {
+        let header = round_trip(&[42; 10], Mode::FrameOfReference);
+        assert_eq!(header, BlockHeader { mode: Mode::FrameOfReference, reference: 42, width: R33::X00 });
+        assert_eq!(round_trip(&[42; 10], Mode::Delta).width, R33::X00);
+        assert_eq!(round_trip(&[], Mode::Delta).words(0), 0);
+    }
+
+    #[test]
+    fn test_delta() {
+        // Deltas wrap, so a jump from 0 to u32::MAX is a delta of -1.
+        let header = round_trip(&[3, 0, u32::MAX, 1, 2], Mode::Delta);
+        assert_eq!(header.reference, 3);
+        assert_eq!(header.width, R33::X03);
+        assert_eq!(round_trip(&[3, 0, u32::MAX, 1, 2], Mode::FrameOfReference).width, R33::X20);
+
+        let mut words = [0u64; 1];
+        let header = BlockHeader { mode: Mode::Delta, reference: 10, width: R33::X04 };
+        assert_eq!(header.pack(&[10, 11, 9, 13], &mut words), 1);
+        assert_eq!(words[0], 0x8320);
+    }
+
+    #[test]
+    #[should_panic(expected = "value does not fit in block width")]
+    fn test_too_narrow() {
+        let header = BlockHeader { mode: Mode::FrameOfReference, reference: 0, width: R33::X03 };
+        header.pack(&[1, 8], &mut [0; 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "value does not fit in block width")]
+    fn test_below_reference() {
+        let header = BlockHeader { mode: Mode::FrameOfReference, reference: 5, width: R33::X08 };
+        header.pack(&[5, 4], &mut [0; 1]);
+    }
+
+    #[test]
+    fn test_reference_wraps() {
+        let header = BlockHeader { mode: Mode::FrameOfReference, reference: u32::MAX, width: R33::X02 };
+        let mut out = [0u32; 3];
+        header.unpack(&[0b10_01_00], &mut out);
+        assert_eq!(out, [u32::MAX, 0, 1]);
+    }
+}
@@ -0,0 +1,237 @@
+use core::fmt;
+
+/// A bit field type with a fixed width of 1 to 32 bits.
+///
+/// Implemented by all of the U1 through U32 types. Values are moved in and out of
+/// the low `WIDTH` bits of a u32.
+pub trait BitWidth: Copy {
+    /// The width of the type in bits.
+    const WIDTH: u32;
+    /// A mask covering the low `WIDTH` bits.
+    const MASK: u32;
+
+    /// Returns the value as the low `WIDTH` bits of a u32.
+    fn bits(self) -> u32;
+
+    /// Constructs the value from the low `WIDTH` bits of `bits`, ignoring any higher bits.
+    fn from_bits_truncate(bits: u32) -> Self;
+}
+
+/// An error returned when a slice of primitives is cast to a slice of U or R values and
+/// an element is out of range.
+///
+/// U7 through U32 are `#[repr(transparent)]` wrappers around u8, u16 and u32, and U1
+/// through U6 and the R types are `#[repr(u8)]` and `#[repr(usize)]` enums, so
+/// `try_cast_slice` can view a slice of primitives as a slice of values after checking each
+/// element once.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut samples = [0x123u16, 0xfff, 0x000];
+/// let values = U12::try_cast_slice(&samples).unwrap();
+/// assert_eq!(values[1], U12::from(0xfffu16));
+/// assert_eq!(U12::as_primitive_slice(values), samples);
+///
+/// samples[2] = 0x1000;
+/// assert_eq!(U12::try_cast_slice_mut(&mut samples), Err(RangeError { index: 2 }));
+/// assert_eq!(R3::try_cast_slice(&[0, 2, 3, 1]).unwrap_err().index, 2);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeError {
+    /// The index of the first element that is out of range.
+    pub index: usize,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value at index {} is out of range", self.index)
+    }
+}
+
+/// A primitive unsigned integer used as a container for bit fields.
+///
+/// Implemented for u8, u16, u32 and u64.
+pub trait Word: Copy + PartialEq + fmt::Debug {
+    /// The width of the container in bits.
+    const BITS: u32;
+    /// A value with all bits cleared.
+    const ZERO: Self;
+
+    /// Returns the value zero-extended to a u64.
+    fn to_u64(self) -> u64;
+
+    /// Constructs the value from the low `BITS` bits of `value`.
+    fn from_u64_truncate(value: u64) -> Self;
+}
+
+macro_rules! impl_word {
+    ($t:ty, $bits:expr) => (
+        impl Word for $t {
+            const BITS: u32 = $bits;
+            const ZERO: Self = 0;
+
+            #[inline]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            #[inline]
+            fn from_u64_truncate(value: u64) -> Self {
+                value as $t
+            }
+        }
+
+        impl FieldExt for $t {}
+    )
+}
+
+impl_word!(u8, 8);
+impl_word!(u16, 16);
+impl_word!(u32, 32);
+impl_word!(u64, 64);
+
+/// Returns the mask for a field of type `T` at `offset`, shifted into position.
+#[inline]
+fn field_mask<T: BitWidth>(offset: u32) -> u64 {
+    (T::MASK as u64) << offset
+}
+
+/// Safe bit field extraction and insertion for primitive unsigned integers.
+///
+/// The `get_field`, `with_field` and `set_field` methods take the field offset as an
+/// argument and panic if the field does not fit within the word. The `_at` variants
+/// take the offset as a const parameter and check the field at compile time.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let v: u32 = 0b0000_0000_1000_0000;
+/// assert_eq!(v.get_field::<U4>(4), U4::B1000);
+/// assert_eq!(v.get_field_at::<4, U4>(), U4::B1000);
+///
+/// let v = v.with_field_at::<0>(U4::B0011);
+/// assert_eq!(v, 0b0000_0000_1000_0011);
+///
+/// let mut v = v;
+/// v.set_field(8, U8::from(0xa5u8));
+/// assert_eq!(v, 0b1010_0101_1000_0011);
+/// ```
+pub trait FieldExt: Word {
+    /// Extracts the field of type `T` starting at bit `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    fn get_field<T: BitWidth>(self, offset: u32) -> T {
+        assert!(offset <= Self::BITS && T::WIDTH <= Self::BITS - offset);
+        T::from_bits_truncate((self.to_u64() >> offset) as u32)
+    }
+
+    /// Returns a copy of the word with the field of type `T` starting at bit `offset`
+    /// replaced by `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    fn with_field<T: BitWidth>(self, offset: u32, value: T) -> Self {
+        assert!(offset <= Self::BITS && T::WIDTH <= Self::BITS - offset);
+        let mask = field_mask::<T>(offset);
+        let bits = (value.bits() as u64) << offset;
+        Self::from_u64_truncate((self.to_u64() & !mask) | bits)
+    }
+
+    /// Replaces the field of type `T` starting at bit `offset` with `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the word.
+    #[inline]
+    fn set_field<T: BitWidth>(&mut self, offset: u32, value: T) {
+        *self = self.with_field(offset, value);
+    }
+
+    /// Extracts the field of type `T` starting at bit `OFFSET`.
+    ///
+    /// Fails to compile if the field extends past the end of the word.
+    #[inline]
+    fn get_field_at<const OFFSET: u32, T: BitWidth>(self) -> T {
+        const { assert!(OFFSET + T::WIDTH <= Self::BITS, "field does not fit in word") };
+        T::from_bits_truncate((self.to_u64() >> OFFSET) as u32)
+    }
+
+    /// Returns a copy of the word with the field starting at bit `OFFSET` replaced by `value`.
+    ///
+    /// Fails to compile if the field extends past the end of the word.
+    #[inline]
+    fn with_field_at<const OFFSET: u32>(self, value: impl BitWidth) -> Self {
+        with_field_at::<OFFSET, _, _>(self, value)
+    }
+
+    /// Replaces the field starting at bit `OFFSET` with `value`.
+    ///
+    /// Fails to compile if the field extends past the end of the word.
+    #[inline]
+    fn set_field_at<const OFFSET: u32>(&mut self, value: impl BitWidth) {
+        *self = with_field_at::<OFFSET, _, _>(*self, value);
+    }
+}
+
+#[inline]
+fn with_field_at<const OFFSET: u32, W: Word, T: BitWidth>(word: W, value: T) -> W {
+    const { assert!(OFFSET + T::WIDTH <= W::BITS, "field does not fit in word") };
+    let mask = field_mask::<T>(OFFSET);
+    let bits = (value.bits() as u64) << OFFSET;
+    W::from_u64_truncate((word.to_u64() & !mask) | bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {U1, U3, U4, U8, U12, U24, U32};
+
+    #[test]
+    fn test_get_field() {
+        let v: u32 = 0x4002_1a3c;
+        assert_eq!(v.get_field::<U4>(0), U4::B1100);
+        assert_eq!(v.get_field::<U4>(4), U4::B0011);
+        assert_eq!(v.get_field::<U12>(4), U12::from(0x1a3u16));
+        assert_eq!(v.get_field::<U1>(30), U1::B1);
+        assert_eq!(v.get_field::<U32>(0), U32::from(0x4002_1a3cu32));
+        assert_eq!(v.get_field_at::<28, U4>(), U4::B0100);
+
+        let v: u8 = 0b1010_0101;
+        assert_eq!(v.get_field::<U3>(5), U3::B101);
+        assert_eq!(v.get_field_at::<0, U8>(), U8::from(0xa5u8));
+
+        let v: u64 = 0xdead_beef_0000_0000;
+        assert_eq!(v.get_field::<U32>(32), U32::from(0xdead_beefu32));
+        assert_eq!(v.get_field_at::<60, U4>(), U4::B1101);
+    }
+
+    #[test]
+    fn test_with_field() {
+        let v: u16 = 0xffff;
+        assert_eq!(v.with_field(4, U4::B0000), 0xff0f);
+        assert_eq!(v.with_field_at::<12>(U4::B0101), 0x5fff);
+        assert_eq!(0u16.with_field(15, U1::B1), 0x8000);
+
+        let mut v: u32 = 0;
+        v.set_field(4, U12::from(0xabcu16));
+        v.set_field_at::<28>(U4::B1111);
+        assert_eq!(v, 0xf000_abc0);
+        v.set_field(4, U12::from(0x001u16));
+        assert_eq!(v, 0xf000_0010);
+
+        let v: u64 = 0;
+        assert_eq!(v.with_field(40, U24::from(0xff_ffffu32)), 0xffff_ff00_0000_0000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_field_overflow() {
+        0u8.get_field::<U4>(5);
+    }
+}
@@ -0,0 +1,257 @@
+use bits::BitWidth;
+
+/// The order of the bytes of a buffer when it is viewed as a single integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// The first byte is the least significant.
+    LittleEndian,
+    /// The first byte is the most significant.
+    BigEndian,
+}
+
+/// How the bits of a buffer are numbered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitNumbering {
+    /// Bit 0 is the least significant bit.
+    Lsb0,
+    /// Bit 0 is the most significant bit, as in PowerPC manuals and network RFC diagrams.
+    Msb0,
+}
+
+/// The byte order and bit numbering used to locate fields within a byte buffer.
+///
+/// The buffer is viewed as a single integer in `bytes` order, and its bits are numbered
+/// according to `bits`. A field of width `w` at offset `n` covers bits `n` to `n + w - 1`,
+/// with the most significant bit of the value at the most significant end of the range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitOrder {
+    /// The byte order of the buffer.
+    pub bytes: ByteOrder,
+    /// The bit numbering of the buffer.
+    pub bits: BitNumbering,
+}
+
+impl BitOrder {
+    /// Little-endian bytes with bit 0 the least significant bit of the first byte.
+    pub const LE_LSB0: BitOrder = BitOrder::new(ByteOrder::LittleEndian, BitNumbering::Lsb0);
+    /// Little-endian bytes with bit 0 the most significant bit of the last byte.
+    pub const LE_MSB0: BitOrder = BitOrder::new(ByteOrder::LittleEndian, BitNumbering::Msb0);
+    /// Big-endian bytes with bit 0 the least significant bit of the last byte.
+    pub const BE_LSB0: BitOrder = BitOrder::new(ByteOrder::BigEndian, BitNumbering::Lsb0);
+    /// Big-endian bytes with bit 0 the most significant bit of the first byte, as in
+    /// network RFC diagrams.
+    pub const BE_MSB0: BitOrder = BitOrder::new(ByteOrder::BigEndian, BitNumbering::Msb0);
+
+    /// Constructs a bit order.
+    #[inline]
+    pub const fn new(bytes: ByteOrder, bits: BitNumbering) -> Self {
+        BitOrder { bytes, bits }
+    }
+
+    /// Returns the index of the byte holding bit `n` of a `len` byte buffer, counting
+    /// bits from the least significant bit of the buffer.
+    #[inline]
+    fn byte(self, len: usize, n: usize) -> usize {
+        match self.bytes {
+            ByteOrder::LittleEndian => n / 8,
+            ByteOrder::BigEndian => len - 1 - n / 8,
+        }
+    }
+
+    /// Returns the position of the lowest bit of a field of width `width` at `offset`,
+    /// counting from the least significant bit of a `len` byte buffer.
+    ///
+    /// Panics if the field extends past the end of the buffer.
+    #[inline]
+    fn lsb(self, len: usize, offset: usize, width: usize) -> usize {
+        assert!(offset <= len * 8 && width <= len * 8 - offset, "field does not fit in buffer");
+        match self.bits {
+            BitNumbering::Lsb0 => offset,
+            BitNumbering::Msb0 => len * 8 - offset - width,
+        }
+    }
+}
+
+/// Bit field extraction and insertion for byte buffers.
+///
+/// Implemented for `[u8]`. Fields of up to 32 bits may start at any bit offset and
+/// straddle several bytes. Their position is given by a `BitOrder`, which selects little-
+/// or big-endian bytes and LSB0 or MSB0 bit numbering.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// // The first word of an IPv4 header, numbered as in RFC 791.
+/// let mut header = [0x45, 0x00, 0x00, 0x54];
+/// assert_eq!(header.get_field::<U4>(0, BitOrder::BE_MSB0), U4::B0100);
+/// assert_eq!(header.get_field::<U4>(4, BitOrder::BE_MSB0), U4::B0101);
+/// assert_eq!(header.get_field::<U16>(16, BitOrder::BE_MSB0), U16::from(84u16));
+///
+/// // A 12-bit field straddling the second and third bytes.
+/// header.set_field(12, U12::from(0xabcu16), BitOrder::BE_MSB0);
+/// assert_eq!(header, [0x45, 0x0a, 0xbc, 0x54]);
+///
+/// // The same buffer as a little-endian u32.
+/// assert_eq!(header.get_field::<U8>(16, BitOrder::LE_LSB0), U8::from(0xbcu8));
+/// ```
+pub trait ByteFieldExt {
+    /// Extracts the `width` bit field at bit `offset` into the low bits of a u32.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32 or the field extends past the end of the buffer.
+    fn get_bits(&self, offset: usize, width: u32, order: BitOrder) -> u32;
+
+    /// Replaces the `width` bit field at bit `offset` with the low bits of `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32 or the field extends past the end of the buffer.
+    fn set_bits(&mut self, offset: usize, width: u32, value: u32, order: BitOrder);
+
+    /// Extracts the field of type `T` at bit `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the buffer.
+    #[inline]
+    fn get_field<T: BitWidth>(&self, offset: usize, order: BitOrder) -> T {
+        T::from_bits_truncate(self.get_bits(offset, T::WIDTH, order))
+    }
+
+    /// Replaces the field of type `T` at bit `offset` with `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the buffer.
+    #[inline]
+    fn set_field<T: BitWidth>(&mut self, offset: usize, value: T, order: BitOrder) {
+        self.set_bits(offset, T::WIDTH, value.bits(), order)
+    }
+}
+
+/// Returns the mask covering the low `width` bits, for `width` up to 32.
+#[inline]
+fn mask(width: u32) -> u64 {
+    assert!(width <= 32, "field is wider than 32 bits");
+    (1u64 << width) - 1
+}
+
+impl ByteFieldExt for [u8] {
+    #[inline]
+    fn get_bits(&self, offset: usize, width: u32, order: BitOrder) -> u32 {
+        let mask = mask(width);
+        if width == 0 {
+            return 0;
+        }
+        let len = self.len();
+        let lsb = order.lsb(len, offset, width as usize);
+        let (first, last) = (lsb / 8, (lsb + width as usize - 1) / 8);
+        let mut bits = 0u64;
+        for n in first..=last {
+            bits |= (self[order.byte(len, n * 8)] as u64) << ((n - first) * 8);
+        }
+        ((bits >> (lsb % 8)) & mask) as u32
+    }
+
+    #[inline]
+    fn set_bits(&mut self, offset: usize, width: u32, value: u32, order: BitOrder) {
+        let mask = mask(width);
+        if width == 0 {
+            return;
+        }
+        let len = self.len();
+        let lsb = order.lsb(len, offset, width as usize);
+        let (first, last) = (lsb / 8, (lsb + width as usize - 1) / 8);
+        let mask = mask << (lsb % 8);
+        let bits = ((value as u64) << (lsb % 8)) & mask;
+        for n in first..=last {
+            let shift = (n - first) * 8;
+            let (m, b) = ((mask >> shift) as u8, (bits >> shift) as u8);
+            let byte = &mut self[order.byte(len, n * 8)];
+            *byte = (*byte & !m) | b;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {U1, U3, U4, U8, U12, U16, U32};
+
+    #[test]
+    fn test_le_lsb0() {
+        // The same as a little-endian integer.
+        let buf = 0x1234_5678_9abc_def0u64.to_le_bytes();
+        assert_eq!(buf.get_field::<U32>(0, BitOrder::LE_LSB0), U32::from(0x9abc_def0u32));
+        assert_eq!(buf.get_field::<U32>(28, BitOrder::LE_LSB0), U32::from(0x2345_6789u32));
+        assert_eq!(buf.get_field::<U4>(60, BitOrder::LE_LSB0), U4::B0001);
+        assert_eq!(buf.get_field::<U12>(30, BitOrder::LE_LSB0), U12::from(0x9e2u16));
+    }
+
+    #[test]
+    fn test_be_lsb0() {
+        // The same as a big-endian integer.
+        let buf = 0x1234_5678u32.to_be_bytes();
+        assert_eq!(buf.get_field::<U8>(24, BitOrder::BE_LSB0), U8::from(0x12u8));
+        assert_eq!(buf.get_field::<U12>(6, BitOrder::BE_LSB0), U12::from(0x159u16));
+        assert_eq!(buf.get_field::<U1>(0, BitOrder::BE_LSB0), U1::B0);
+    }
+
+    #[test]
+    fn test_msb0() {
+        let buf = [0b1010_0000, 0x00, 0x00, 0x01];
+        assert_eq!(buf.get_field::<U3>(0, BitOrder::BE_MSB0), U3::B101);
+        assert_eq!(buf.get_field::<U1>(31, BitOrder::BE_MSB0), U1::B1);
+        // LE_MSB0 numbers from the most significant bit of the last byte.
+        assert_eq!(buf.get_field::<U8>(0, BitOrder::LE_MSB0), U8::from(0x01u8));
+        assert_eq!(buf.get_field::<U3>(24, BitOrder::LE_MSB0), U3::B101);
+    }
+
+    #[test]
+    fn test_set_field() {
+        for &order in &[BitOrder::LE_LSB0, BitOrder::LE_MSB0, BitOrder::BE_LSB0, BitOrder::BE_MSB0] {
+            for offset in 0..=40 {
+                let mut buf = [0xa5u8; 9];
+                let before = buf;
+                buf.set_field(offset, U32::from(0x0123_4567u32), order);
+                assert_eq!(buf.get_field::<U32>(offset, order), U32::from(0x0123_4567u32));
+                buf.set_field(offset, U16::from(0xffffu16), order);
+                assert_eq!(buf.get_field::<U16>(offset, order), U16::from(0xffffu16));
+
+                // Restoring the field restores the buffer, so no other bits were touched.
+                buf.set_field(offset, before.get_field::<U32>(offset, order), order);
+                assert_eq!(buf, before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut buf = [0u8; 3];
+        buf.set_bits(3, 17, 0x1_ffff, BitOrder::BE_MSB0);
+        assert_eq!(buf, [0x1f, 0xff, 0xf0]);
+        assert_eq!(buf.get_bits(2, 5, BitOrder::BE_MSB0), 0b01111);
+        assert_eq!(buf.get_bits(24, 0, BitOrder::BE_MSB0), 0);
+        buf.set_bits(0, 4, 0xff, BitOrder::LE_LSB0);
+        assert_eq!(buf, [0x1f, 0xff, 0xf0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "field is wider than 32 bits")]
+    fn test_too_wide() {
+        [0u8; 8].get_bits(0, 33, BitOrder::LE_LSB0);
+    }
+
+    #[test]
+    #[should_panic(expected = "field does not fit in buffer")]
+    fn test_overflow() {
+        [0u8; 4].get_field::<U4>(29, BitOrder::BE_MSB0);
+    }
+
+    #[test]
+    #[should_panic(expected = "field does not fit in buffer")]
+    fn test_overflow_set() {
+        [0u8; 2].set_field(0, U32::from(0u32), BitOrder::LE_LSB0);
+    }
+}
@@ -0,0 +1,483 @@
+//! Variable-length integer codes over bit streams.
+//!
+//! - `ue` and `se` are the unsigned and signed Exp-Golomb codes of H.264 and H.265.
+//! - Unary and Rice codes use a run of zeros terminated by a one for the quotient, and a
+//!   Rice code with a `U_k` type parameter stores the remainder as a k-bit `U_k`.
+//! - LEB128 varints store seven bits per byte, least significant group first, with the
+//!   high bit of each byte set if more bytes follow. They need not be byte aligned.
+//!
+//! The `_as` decoders check that the value fits in a U type, for fields that the format
+//! bounds. All decoders leave the reader unchanged if they fail, and encoders leave the
+//! writer unchanged if the code does not fit.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits::codes::*;
+//!
+//! let mut buf = [0u8; 4];
+//! let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+//! write_ue(&mut w, 3).unwrap();
+//! write_se(&mut w, -2).unwrap();
+//! write_rice::<U2>(&mut w, 9).unwrap();
+//! assert_eq!(w.position(), 5 + 5 + 5);
+//! assert_eq!(buf[..2], [0b0010_0001, 0b0100_1010]);
+//!
+//! let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+//! assert_eq!(read_ue_as::<U2>(&mut r), Ok(U2::B11));
+//! assert_eq!(read_se(&mut r), Ok(-2));
+//! assert_eq!(read_rice::<U2>(&mut r), Ok(9));
+//! ```
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use bits::BitWidth;
+use stream::{BitReader, BitWriter, StreamError};
+
+/// An error encoding or decoding a variable-length code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeError {
+    /// The input ended before the code was decoded.
+    Underrun,
+    /// The output buffer has no room for the code.
+    Overflow,
+    /// A decoded value does not fit in the requested type.
+    OutOfRange,
+    /// The input is not a valid code, such as an Exp-Golomb prefix of more than 32 zeros.
+    Invalid,
+}
+
+impl From<StreamError> for CodeError {
+    #[inline]
+    fn from(other: StreamError) -> Self {
+        match other {
+            StreamError::Underrun => CodeError::Underrun,
+            StreamError::Overflow => CodeError::Overflow,
+        }
+    }
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CodeError::Underrun => f.write_str("unexpected end of input"),
+            CodeError::Overflow => f.write_str("output buffer is full"),
+            CodeError::OutOfRange => f.write_str("value is out of range"),
+            CodeError::Invalid => f.write_str("invalid code"),
+        }
+    }
+}
+
+/// Runs `f` on `r`, restoring the position of `r` if it fails.
+#[inline]
+fn restore<'a, T, F>(r: &mut BitReader<'a>, f: F) -> Result<T, CodeError>
+where
+    F: FnOnce(&mut BitReader<'a>) -> Result<T, CodeError>,
+{
+    let start = r.clone();
+    f(r).inspect_err(|_| *r = start)
+}
+
+/// Checks that `value` fits in `T`.
+#[inline]
+fn bounded<T: BitWidth>(value: u64) -> Result<T, CodeError> {
+    if value > T::MASK as u64 {
+        return Err(CodeError::OutOfRange);
+    }
+    Ok(T::from_bits_truncate(value as u32))
+}
+
+/// Writes `n` zeros, for any `n`.
+fn write_zeros(w: &mut BitWriter, mut n: u32) -> Result<(), CodeError> {
+    while n > 0 {
+        let chunk = n.min(32);
+        w.write_bits(0, chunk)?;
+        n -= chunk;
+    }
+    Ok(())
+}
+
+/// Counts and consumes zeros up to and including the terminating one, failing with
+/// `Invalid` if there are more than `max` zeros.
+fn read_zeros(r: &mut BitReader, max: u32) -> Result<u32, CodeError> {
+    let mut n = 0;
+    while !r.read_bool()? {
+        if n == max {
+            return Err(CodeError::Invalid);
+        }
+        n += 1;
+    }
+    Ok(n)
+}
+
+/// Zigzag maps a signed value to an unsigned one: 0, -1, 1, -2, ... map to 0, 1, 2, 3, ...
+///
+/// ```
+/// use bobbin_bits::codes::{zigzag_decode, zigzag_encode};
+///
+/// assert_eq!(zigzag_encode(-1), 1);
+/// assert_eq!(zigzag_encode(1), 2);
+/// assert_eq!(zigzag_encode(i32::MIN), u32::MAX);
+/// assert_eq!(zigzag_decode(4), 2);
+/// ```
+#[inline]
+pub const fn zigzag_encode(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// Reverses `zigzag_encode`.
+#[inline]
+pub const fn zigzag_decode(value: u32) -> i32 {
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+/// Writes an Exp-Golomb code for values up to 2^32.
+fn write_exp_golomb(w: &mut BitWriter, value: u64) -> Result<(), CodeError> {
+    let code = value + 1;
+    let zeros = 63 - code.leading_zeros();
+    if w.remaining() < (2 * zeros + 1) as usize {
+        return Err(CodeError::Overflow);
+    }
+    // The code is `zeros + 1` bits long and starts with the terminating one.
+    write_zeros(w, zeros)?;
+    w.write_bool(true)?;
+    w.write_bits((code & ((1 << zeros) - 1)) as u32, zeros)?;
+    Ok(())
+}
+
+/// Reads an Exp-Golomb code with a prefix of up to 32 zeros.
+fn read_exp_golomb(r: &mut BitReader) -> Result<u64, CodeError> {
+    let zeros = read_zeros(r, 32)?;
+    let suffix = r.read_bits(zeros)? as u64;
+    Ok((1 << zeros) - 1 + suffix)
+}
+
+/// Writes `value` as an unsigned Exp-Golomb code, `ue(v)`.
+pub fn write_ue(w: &mut BitWriter, value: u32) -> Result<(), CodeError> {
+    write_exp_golomb(w, value as u64)
+}
+
+/// Reads an unsigned Exp-Golomb code, `ue(v)`.
+pub fn read_ue(r: &mut BitReader) -> Result<u32, CodeError> {
+    restore(r, |r| u32::try_from(read_exp_golomb(r)?).map_err(|_| CodeError::OutOfRange))
+}
+
+/// Reads an unsigned Exp-Golomb code into a U type, failing with `OutOfRange` if the value
+/// does not fit.
+pub fn read_ue_as<T: BitWidth>(r: &mut BitReader) -> Result<T, CodeError> {
+    restore(r, |r| bounded(read_exp_golomb(r)?))
+}
+
+/// Writes `value` as a signed Exp-Golomb code, `se(v)`, which maps 0, 1, -1, 2, -2, ...
+/// to the `ue(v)` codes 0, 1, 2, 3, 4, ...
+pub fn write_se(w: &mut BitWriter, value: i32) -> Result<(), CodeError> {
+    let code = if value > 0 { value as u64 * 2 - 1 } else { value.unsigned_abs() as u64 * 2 };
+    write_exp_golomb(w, code)
+}
+
+/// Reads a signed Exp-Golomb code, `se(v)`.
+pub fn read_se(r: &mut BitReader) -> Result<i32, CodeError> {
+    restore(r, |r| {
+        let code = read_exp_golomb(r)? as i64;
+        let value = if code & 1 == 1 { (code + 1) / 2 } else { -(code / 2) };
+        i32::try_from(value).map_err(|_| CodeError::OutOfRange)
+    })
+}
+
+/// Writes `value` as a unary code: `value` zeros followed by a one.
+pub fn write_unary(w: &mut BitWriter, value: u32) -> Result<(), CodeError> {
+    if w.remaining() < value as usize + 1 {
+        return Err(CodeError::Overflow);
+    }
+    write_zeros(w, value)?;
+    w.write_bool(true)?;
+    Ok(())
+}
+
+/// Reads a unary code, failing with `Invalid` if there are more than `max` zeros.
+pub fn read_unary(r: &mut BitReader, max: u32) -> Result<u32, CodeError> {
+    restore(r, |r| read_zeros(r, max))
+}
+
+/// Writes `value` as a Rice code with parameter `K::WIDTH`: the quotient
+/// `value >> K::WIDTH` in unary, followed by the remainder as a `K`.
+pub fn write_rice<K: BitWidth>(w: &mut BitWriter, value: u32) -> Result<(), CodeError> {
+    let quotient = (value as u64 >> K::WIDTH) as u32;
+    if w.remaining() < quotient as usize + 1 + K::WIDTH as usize {
+        return Err(CodeError::Overflow);
+    }
+    write_unary(w, quotient)?;
+    w.write(K::from_bits_truncate(value))?;
+    Ok(())
+}
+
+/// Reads a Rice code with parameter `K::WIDTH`, failing with `OutOfRange` if the value
+/// does not fit in a u32.
+pub fn read_rice<K: BitWidth>(r: &mut BitReader) -> Result<u32, CodeError> {
+    restore(r, |r| {
+        // Any longer quotient would overflow, so there is no need to scan further.
+        let quotient = read_zeros(r, u32::MAX.checked_shr(K::WIDTH).unwrap_or(0))?;
+        let remainder = r.read::<K>()?.bits();
+        let value = ((quotient as u64) << K::WIDTH) | remainder as u64;
+        if value > u32::MAX as u64 {
+            return Err(CodeError::OutOfRange);
+        }
+        Ok(value as u32)
+    })
+}
+
+/// Reads a Rice code with parameter `K::WIDTH` into a U type, failing with `OutOfRange`
+/// if the value does not fit.
+pub fn read_rice_as<K: BitWidth, T: BitWidth>(r: &mut BitReader) -> Result<T, CodeError> {
+    restore(r, |r| bounded(read_rice::<K>(r)? as u64))
+}
+
+/// Returns the number of bytes in the LEB128 encoding of a value with `bits` significant bits.
+#[inline]
+fn leb128_len(bits: u32) -> usize {
+    bits.max(1).div_ceil(7) as usize
+}
+
+/// Writes `value` as an unsigned LEB128 varint.
+pub fn write_uleb128(w: &mut BitWriter, mut value: u64) -> Result<(), CodeError> {
+    if w.remaining() < leb128_len(64 - value.leading_zeros()) * 8 {
+        return Err(CodeError::Overflow);
+    }
+    loop {
+        let byte = (value & 0x7f) as u32;
+        value >>= 7;
+        if value == 0 {
+            w.write_bits(byte, 8)?;
+            return Ok(());
+        }
+        w.write_bits(byte | 0x80, 8)?;
+    }
+}
+
+/// Reads an unsigned LEB128 varint, failing with `OutOfRange` if it does not fit in a u64.
+pub fn read_uleb128(r: &mut BitReader) -> Result<u64, CodeError> {
+    restore(r, |r| {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = r.read_bits(8)? as u64;
+            if shift == 63 && byte & 0x7e != 0 || shift > 63 {
+                return Err(CodeError::OutOfRange);
+            }
+            value |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    })
+}
+
+/// Reads an unsigned LEB128 varint into a U type, failing with `OutOfRange` if the value
+/// does not fit.
+pub fn read_uleb128_as<T: BitWidth>(r: &mut BitReader) -> Result<T, CodeError> {
+    restore(r, |r| bounded(read_uleb128(r)?))
+}
+
+/// Writes `value` as a signed LEB128 varint.
+pub fn write_sleb128(w: &mut BitWriter, mut value: i64) -> Result<(), CodeError> {
+    // One sign bit plus the significant bits of the magnitude.
+    let bits = 65 - if value < 0 { (!value).leading_zeros() } else { value.leading_zeros() };
+    if w.remaining() < leb128_len(bits) * 8 {
+        return Err(CodeError::Overflow);
+    }
+    loop {
+        let byte = (value & 0x7f) as u32;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            w.write_bits(byte, 8)?;
+            return Ok(());
+        }
+        w.write_bits(byte | 0x80, 8)?;
+    }
+}
+
+/// Reads a signed LEB128 varint, failing with `OutOfRange` if it does not fit in an i64.
+pub fn read_sleb128(r: &mut BitReader) -> Result<i64, CodeError> {
+    restore(r, |r| {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = r.read_bits(8)? as i64;
+            if shift == 63 {
+                // The last byte may only hold the sign bit, extended.
+                if byte & 0x80 != 0 || (byte != 0 && byte != 0x7f) {
+                    return Err(CodeError::OutOfRange);
+                }
+            }
+            value |= (byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stream::StreamOrder;
+    use {U3, U4, U8, U32};
+
+    #[test]
+    fn test_ue() {
+        // ue(0..=7) is 1, 010, 011, 00100, 00101, 00110, 00111, 0001000.
+        let mut buf = [0u8; 5];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        for v in 0..=7 {
+            write_ue(&mut w, v).unwrap();
+        }
+        assert_eq!(w.position(), 1 + 3 * 2 + 5 * 4 + 7);
+        assert_eq!(buf, [0b1010_0110, 0b0100_0010, 0b1001_1000, 0b1110_0010, 0]);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        for v in 0..=7 {
+            assert_eq!(read_ue(&mut r), Ok(v));
+        }
+        // The padding is the start of a longer code.
+        assert_eq!(read_ue(&mut r), Err(CodeError::Underrun));
+        assert_eq!(r.position(), 34);
+    }
+
+    #[test]
+    fn test_ue_limits() {
+        let mut buf = [0u8; 9];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::LsbFirst);
+        write_ue(&mut w, u32::MAX).unwrap();
+        assert_eq!(w.position(), 65);
+        assert_eq!(write_ue(&mut w, 15), Err(CodeError::Overflow));
+        assert_eq!(w.position(), 65);
+
+        let mut r = BitReader::new(&buf, StreamOrder::LsbFirst);
+        assert_eq!(read_ue_as::<U32>(&mut r), Ok(U32::from(u32::MAX)));
+        let mut r = BitReader::new(&buf, StreamOrder::LsbFirst);
+        assert_eq!(read_ue_as::<U8>(&mut r), Err(CodeError::OutOfRange));
+        assert_eq!(r.position(), 0);
+
+        // A prefix of 33 zeros is not a valid code, and 32 zeros with an all-ones suffix
+        // does not fit in a u32.
+        let mut r = BitReader::new(&[0; 9], StreamOrder::MsbFirst);
+        assert_eq!(read_ue(&mut r), Err(CodeError::Invalid));
+        let buf = [0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0x80];
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(read_ue(&mut r), Err(CodeError::OutOfRange));
+        assert_eq!(r.position(), 0);
+    }
+
+    #[test]
+    fn test_se() {
+        let mut buf = [0u8; 32];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        write_se(&mut w, 1).unwrap();
+        write_se(&mut w, -1).unwrap();
+        assert_eq!(w.position(), 6);
+        let values = [0, 2, -2, 1000, -1000, i32::MAX, i32::MIN];
+        for &v in &values {
+            write_se(&mut w, v).unwrap();
+        }
+        assert_eq!(buf[0] >> 2, 0b010_011);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(read_se(&mut r), Ok(1));
+        assert_eq!(read_se(&mut r), Ok(-1));
+        for &v in &values {
+            assert_eq!(read_se(&mut r), Ok(v));
+        }
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for &(s, u) in &[(0, 0), (-1, 1), (1, 2), (-2, 3), (i32::MAX, u32::MAX - 1), (i32::MIN, u32::MAX)] {
+            assert_eq!(zigzag_encode(s), u);
+            assert_eq!(zigzag_decode(u), s);
+        }
+    }
+
+    #[test]
+    fn test_rice() {
+        let mut buf = [0u8; 3];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        write_unary(&mut w, 3).unwrap();
+        write_rice::<U3>(&mut w, 5).unwrap();
+        write_rice::<U3>(&mut w, 21).unwrap();
+        assert_eq!(w.position(), 4 + 4 + 6);
+        assert_eq!(w.as_bytes(), [0b0001_1101, 0b0011_0100]);
+        assert_eq!(write_rice::<U4>(&mut w, 0xff), Err(CodeError::Overflow));
+        assert_eq!(w.position(), 14);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(read_unary(&mut r, 2), Err(CodeError::Invalid));
+        assert_eq!(read_unary(&mut r, 3), Ok(3));
+        assert_eq!(read_rice::<U3>(&mut r), Ok(5));
+        assert_eq!(read_rice_as::<U3, U4>(&mut r), Err(CodeError::OutOfRange));
+        assert_eq!(read_rice_as::<U3, U8>(&mut r), Ok(U8::from(21u8)));
+
+        // The quotient of a Rice code with k = 32 must be zero.
+        let mut r = BitReader::new(&[0x40, 0, 0, 0, 0], StreamOrder::MsbFirst);
+        assert_eq!(read_rice::<U32>(&mut r), Err(CodeError::Invalid));
+    }
+
+    #[test]
+    fn test_uleb128() {
+        let mut buf = [0u8; 16];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        write_uleb128(&mut w, 624_485).unwrap();
+        write_uleb128(&mut w, 0).unwrap();
+        write_uleb128(&mut w, u64::MAX).unwrap();
+        assert_eq!(w.as_bytes()[..5], [0xe5, 0x8e, 0x26, 0x00, 0xff]);
+        assert_eq!(w.as_bytes()[13], 0x01);
+        assert_eq!(write_uleb128(&mut w, u64::MAX), Err(CodeError::Overflow));
+        assert_eq!(w.position(), 14 * 8);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(read_uleb128(&mut r), Ok(624_485));
+        assert_eq!(read_uleb128_as::<U3>(&mut r), Ok(U3::B000));
+        assert_eq!(read_uleb128_as::<U32>(&mut r), Err(CodeError::OutOfRange));
+        assert_eq!(read_uleb128(&mut r), Ok(u64::MAX));
+
+        // Eleven bytes, or a tenth byte with more than one bit, overflow a u64.
+        let mut long = [0xff; 11];
+        long[10] = 0;
+        assert_eq!(read_uleb128(&mut BitReader::new(&long, StreamOrder::MsbFirst)), Err(CodeError::OutOfRange));
+        long[9] = 0x02;
+        assert_eq!(read_uleb128(&mut BitReader::new(&long, StreamOrder::MsbFirst)), Err(CodeError::OutOfRange));
+        assert_eq!(read_uleb128(&mut BitReader::new(&long[..3], StreamOrder::MsbFirst)), Err(CodeError::Underrun));
+    }
+
+    #[test]
+    fn test_sleb128() {
+        let mut buf = [0u8; 32];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::LsbFirst);
+        // Varints need not be byte aligned.
+        w.write_bool(true).unwrap();
+        write_sleb128(&mut w, -123_456).unwrap();
+        assert_eq!(w.position(), 25);
+        let values = [0, -1, 63, -64, 64, -65, i64::MAX, i64::MIN];
+        for &v in &values {
+            write_sleb128(&mut w, v).unwrap();
+        }
+        assert_eq!(w.position(), 25 + (1 + 1 + 1 + 1 + 2 + 2 + 10 + 10) * 8);
+
+        let mut r = BitReader::new(&buf, StreamOrder::LsbFirst);
+        assert_eq!(r.read_bool(), Ok(true));
+        assert_eq!(r.peek_bits(24), Ok(0x78_bb_c0));
+        assert_eq!(read_sleb128(&mut r), Ok(-123_456));
+        for &v in &values {
+            assert_eq!(read_sleb128(&mut r), Ok(v));
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(std::format!("{}", CodeError::Invalid), "invalid code");
+    }
+}
@@ -92,30 +92,110 @@ macro_rules! impl_enum {
         impl $id {
             #[inline]
             /// Returns the primitive representation of the value.
-            pub fn value(&self) -> u8 {
+            pub const fn value(&self) -> u8 {
                 *self as u8
             }
 
+            #[inline]
+            /// Reinterprets a slice of primitives as a slice of values, after checking that
+            /// every element is within the range of the type.
+            pub fn try_cast_slice(slice: &[u8]) -> Result<&[Self], RangeError> {
+                match slice.iter().position(|&v| v & !$mask != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a mutable slice of primitives as a slice of values, after checking
+            /// that every element is within the range of the type.
+            pub fn try_cast_slice_mut(slice: &mut [u8]) -> Result<&mut [Self], RangeError> {
+                match slice.iter().position(|&v| v & !$mask != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a slice of values as a slice of their primitive representations.
+            pub fn as_primitive_slice(slice: &[Self]) -> &[u8] {
+                unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const u8, slice.len()) }
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` little-endian bytes.
+            pub fn to_le_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_le_bytes()[..usize::div_ceil($width, 8)]);
+                bytes
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` big-endian bytes.
+            pub fn to_be_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_be_bytes()[1 - usize::div_ceil($width, 8)..]);
+                bytes
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` little-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_le_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 1];
+                all[..usize::div_ceil($width, 8)].copy_from_slice(&bytes);
+                let v = u8::from_le_bytes(all);
+                if v & !$mask != 0 { Err(RangeError { index: 0 }) } else { Ok(unsafe { Self::from_u8_unchecked(v) }) }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` big-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_be_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 1];
+                all[1 - usize::div_ceil($width, 8)..].copy_from_slice(&bytes);
+                let v = u8::from_be_bytes(all);
+                if v & !$mask != 0 { Err(RangeError { index: 0 }) } else { Ok(unsafe { Self::from_u8_unchecked(v) }) }
+            }
+
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u8_unchecked(other: u8) -> Self {
                 transmute(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u16_unchecked(other: u16) -> Self {
                 transmute(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u32_unchecked(other: u32) -> Self {
                 transmute(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_usize_unchecked(other: usize) -> Self {
                 transmute(other as u8)
             }
@@ -151,6 +231,21 @@ macro_rules! impl_enum {
             }
         }
 
+        impl BitWidth for $id {
+            const WIDTH: u32 = $width;
+            const MASK: u32 = $mask;
+
+            #[inline]
+            fn bits(self) -> u32 {
+                self as u32
+            }
+
+            #[inline]
+            fn from_bits_truncate(bits: u32) -> Self {
+                unsafe { transmute((bits & $mask) as u8) }
+            }
+        }
+
         impl fmt::Debug for $id {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 write!(f, "0b{:0width$b}", *self as u8, width=$width)
@@ -0,0 +1,293 @@
+use core::marker::PhantomData;
+
+use access::{Access, RW};
+use bits::{BitWidth, Word};
+
+/// A bit field descriptor naming a range of bits within a word.
+///
+/// `read` and `write` fail to compile if the field does not fit within the word type
+/// they are used with.
+pub trait BitRange {
+    /// The type of the field value.
+    type Value: BitWidth;
+    /// The access policy of the field.
+    type Access: Access;
+    /// The mask of bits covered by the field, in position.
+    const MASK: u64;
+
+    /// Extracts the field value from `word`.
+    fn read<W: Word>(word: W) -> Self::Value;
+
+    /// Returns a copy of `word` with the field replaced by `value`.
+    fn write<W: Word>(word: W, value: Self::Value) -> W;
+}
+
+/// A field of type `T` starting at bit `OFFSET` of a word, with access policy `A`.
+///
+/// A `Field` type names the field once so that it can be reused across register and
+/// protocol layouts.
+///
+/// The access policy only restricts how the field is used through a `Register`; `read`
+/// and `write` on plain words are always available.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// type Mode = Field<4, U2>;
+///
+/// assert_eq!(Mode::shift(), 4);
+/// assert_eq!(Mode::mask(), 0b0011_0000);
+/// assert_eq!(Mode::read(0b0010_0000u32), U2::B10);
+/// assert_eq!(Mode::write(0xffu8, U2::B00), 0xcf);
+/// ```
+pub struct Field<const OFFSET: u32, T, A = RW> {
+    _value: PhantomData<T>,
+    _access: PhantomData<A>,
+}
+
+impl<const OFFSET: u32, T: BitWidth, A: Access> Field<OFFSET, T, A> {
+    /// The offset of the lowest bit of the field.
+    pub const SHIFT: u32 = OFFSET;
+    /// The width of the field in bits.
+    pub const WIDTH: u32 = T::WIDTH;
+
+    /// Returns the offset of the lowest bit of the field.
+    #[inline]
+    pub const fn shift() -> u32 {
+        OFFSET
+    }
+
+    /// Returns the width of the field in bits.
+    #[inline]
+    pub const fn width() -> u32 {
+        T::WIDTH
+    }
+
+    /// Returns the mask of bits covered by the field, in position.
+    #[inline]
+    pub const fn mask() -> u64 {
+        <Self as BitRange>::MASK
+    }
+}
+
+impl<const OFFSET: u32, T: BitWidth, A: Access> BitRange for Field<OFFSET, T, A> {
+    type Value = T;
+    type Access = A;
+    const MASK: u64 = {
+        assert!(OFFSET + T::WIDTH <= 64, "field does not fit in word");
+        (T::MASK as u64) << OFFSET
+    };
+
+    #[inline]
+    fn read<W: Word>(word: W) -> T {
+        const { assert!(OFFSET + T::WIDTH <= W::BITS, "field does not fit in word") };
+        T::from_bits_truncate((word.to_u64() >> OFFSET) as u32)
+    }
+
+    #[inline]
+    fn write<W: Word>(word: W, value: T) -> W {
+        const { assert!(OFFSET + T::WIDTH <= W::BITS, "field does not fit in word") };
+        let bits = (value.bits() as u64) << OFFSET;
+        W::from_u64_truncate((word.to_u64() & !Self::MASK) | bits)
+    }
+}
+
+/// The segments of a `Scattered` field: a tuple of `Field`s, listed from the segment
+/// holding the most significant bits of the value to the one holding the least.
+pub trait Segments {
+    /// The total width of the segments in bits.
+    const WIDTH: u32;
+    /// The mask of bits covered by the segments, in position.
+    ///
+    /// Evaluating the mask fails to compile if two segments overlap.
+    const MASK: u64;
+
+    /// Gathers the segments of `word` into a value.
+    fn gather(word: u64) -> u64;
+
+    /// Returns a copy of `word` with `value` scattered into the segments.
+    fn scatter(word: u64, value: u64) -> u64;
+}
+
+macro_rules! impl_segments {
+    ($($s:ident),*) => {
+        impl<$($s: BitRange),*> Segments for ($($s,)*) {
+            const WIDTH: u32 = 0 $(+ $s::Value::WIDTH)*;
+            const MASK: u64 = {
+                let mut mask = 0;
+                $(
+                    assert!(mask & $s::MASK == 0, "field segments overlap");
+                    mask |= $s::MASK;
+                )*
+                mask
+            };
+
+            #[inline]
+            fn gather(word: u64) -> u64 {
+                let mut value = 0;
+                $(value = value << $s::Value::WIDTH | $s::read(word).bits() as u64;)*
+                value
+            }
+
+            #[inline]
+            fn scatter(mut word: u64, value: u64) -> u64 {
+                let mut shift = Self::WIDTH;
+                $(
+                    shift -= $s::Value::WIDTH;
+                    word = $s::write(word, $s::Value::from_bits_truncate((value >> shift) as u32));
+                )*
+                word
+            }
+        }
+    };
+}
+
+impl_segments!(A, B);
+impl_segments!(A, B, C);
+impl_segments!(A, B, C, D);
+impl_segments!(A, B, C, D, E);
+impl_segments!(A, B, C, D, E, F);
+impl_segments!(A, B, C, D, E, F, G);
+impl_segments!(A, B, C, D, E, F, G, H);
+
+/// A field of type `T` split across the disjoint bit ranges `S`, with access policy `A`.
+///
+/// The segments are listed from the one holding the most significant bits of the value,
+/// as in a concatenation `{word[15:12], word[5:0]}`. Using the field fails to compile if
+/// the segment widths do not add up to the width of `T` or if two segments overlap.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// // A U10 made of bits [15:12] followed by bits [5:0]
+/// type Imm = Scattered<U10, (Field<12, U4>, Field<0, U6>)>;
+///
+/// assert_eq!(Imm::mask(), 0xf03f);
+/// assert_eq!(Imm::read(0x9015u16), U10::from(0x255u16));
+/// assert_eq!(Imm::write(0u16, U10::from(0x3ffu16)), 0xf03f);
+/// ```
+///
+/// ```compile_fail
+/// use bobbin_bits::*;
+///
+/// // The segments only add up to 9 bits.
+/// type Imm = Scattered<U10, (Field<12, U3>, Field<0, U6>)>;
+/// Imm::read(0u16);
+/// ```
+///
+/// ```compile_fail
+/// use bobbin_bits::*;
+///
+/// // Bit 5 is in both segments.
+/// type Imm = Scattered<U10, (Field<5, U4>, Field<0, U6>)>;
+/// Imm::read(0u16);
+/// ```
+pub struct Scattered<T, S, A = RW> {
+    _value: PhantomData<T>,
+    _segments: PhantomData<S>,
+    _access: PhantomData<A>,
+}
+
+impl<T: BitWidth, S: Segments, A: Access> Scattered<T, S, A> {
+    /// The width of the field in bits.
+    pub const WIDTH: u32 = T::WIDTH;
+
+    /// Returns the width of the field in bits.
+    #[inline]
+    pub const fn width() -> u32 {
+        T::WIDTH
+    }
+
+    /// Returns the mask of bits covered by the field, in position.
+    #[inline]
+    pub const fn mask() -> u64 {
+        <Self as BitRange>::MASK
+    }
+}
+
+impl<T: BitWidth, S: Segments, A: Access> BitRange for Scattered<T, S, A> {
+    type Value = T;
+    type Access = A;
+    const MASK: u64 = {
+        assert!(S::WIDTH == T::WIDTH, "field segment widths do not add up to the field width");
+        S::MASK
+    };
+
+    #[inline]
+    fn read<W: Word>(word: W) -> T {
+        const { assert!(W::BITS == 64 || Self::MASK >> W::BITS == 0, "field does not fit in word") };
+        T::from_bits_truncate(S::gather(word.to_u64()) as u32)
+    }
+
+    #[inline]
+    fn write<W: Word>(word: W, value: T) -> W {
+        const { assert!(W::BITS == 64 || Self::MASK >> W::BITS == 0, "field does not fit in word") };
+        W::from_u64_truncate(S::scatter(word.to_u64(), value.bits() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {U1, U2, U4, U5, U6, U8, U12, U16, U20, U32};
+
+    #[test]
+    fn test_field_constants() {
+        type F = Field<4, U12>;
+        assert_eq!(F::SHIFT, 4);
+        assert_eq!(F::WIDTH, 12);
+        assert_eq!(F::mask(), 0xfff0);
+        assert_eq!(Field::<31, U1>::mask(), 0x8000_0000);
+        assert_eq!(Field::<32, U32>::mask(), 0xffff_ffff_0000_0000);
+    }
+
+    #[test]
+    fn test_field_read_write() {
+        type Lo = Field<0, U4>;
+        type Hi = Field<4, U4>;
+        type Byte = Field<8, U8>;
+
+        let w = 0x1a3cu16;
+        assert_eq!(Lo::read(w), U4::B1100);
+        assert_eq!(Hi::read(w), U4::B0011);
+        assert_eq!(Byte::read(w), U8::from(0x1au8));
+
+        let w = Hi::write(w, U4::B1111);
+        assert_eq!(w, 0x1afc);
+        let w = Byte::write(w, U8::from(0u8));
+        assert_eq!(w, 0x00fc);
+
+        assert_eq!(Field::<60, U4>::write(0u64, U4::B1010), 0xa000_0000_0000_0000);
+    }
+
+    #[test]
+    fn test_scattered() {
+        // A RISC-V B-type immediate: imm[12|10:5] in bits [31:25], imm[4:1|11] in bits [11:7]
+        type Imm = Scattered<U12, (Field<31, U1>, Field<7, U1>, Field<25, U6>, Field<8, U4>)>;
+        assert_eq!(Imm::width(), 12);
+        assert_eq!(Imm::mask(), 0xfe00_0f80);
+
+        // beq x0, x0, -4 encodes the offset -4 >> 1 = 0xffe
+        let word = 0xfe00_0ee3u32;
+        assert_eq!(Imm::read(word), U12::from(0xffeu16));
+        assert_eq!(Imm::write(word & !Imm::mask() as u32, U12::from(0xffeu16)), word);
+
+        // Segments do not need to be in bit order and are independent of each other.
+        type Swapped = Scattered<U8, (Field<0, U4>, Field<4, U4>)>;
+        assert_eq!(Swapped::read(0xa5u8), U8::from(0x5au8));
+        assert_eq!(Swapped::write(0xffu8, U8::from(0x12u8)), 0x21);
+
+        type Wide = Scattered<U20, (Field<60, U4>, Field<0, U16>)>;
+        assert_eq!(Wide::write(0u64, U20::from(0xa_bcdeu32)), 0xa000_0000_0000_bcde);
+        assert_eq!(Wide::read(0xa000_0000_0000_bcdeu64), U20::from(0xa_bcdeu32));
+    }
+
+    #[test]
+    fn test_scattered_segments() {
+        type S = (Field<10, U2>, Field<0, U5>, Field<6, U1>);
+        assert_eq!(<S as Segments>::WIDTH, 8);
+        assert_eq!(<S as Segments>::MASK, 0b1100_0101_1111);
+        assert_eq!(S::gather(0b1000_0101_0011), 0b1010_0111);
+        assert_eq!(S::scatter(0, 0b1010_0111), 0b1000_0101_0011);
+    }
+}
@@ -0,0 +1,139 @@
+use core::marker::PhantomData;
+
+use access::Writable;
+use bits::Word;
+use field::BitRange;
+
+/// A set of field assignments to be applied to a word in a single update.
+///
+/// `Register::modify_fields()` applies a set with a single read-modify-write, and
+/// `Register::write_fields()` with a single write on top of the register's reset value.
+/// Assigning overlapping fields to different values panics.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// type En = Field<0, U1>;
+/// type Mode = Field<4, U2>;
+/// type Prescale = Field<8, U8>;
+///
+/// let set = FieldSet::<u32>::new()
+///     .with::<En>(U1::B1)
+///     .with::<Mode>(U2::B10)
+///     .with::<Prescale>(U8::from(0x40u8));
+///
+/// assert_eq!(set.mask(), 0x0000_ff31);
+/// assert_eq!(set.value(), 0x0000_4021);
+/// assert_eq!(set.apply(0xffff_ffff), 0xffff_40ef);
+/// ```
+///
+/// Only writable fields can be assigned:
+///
+/// ```compile_fail
+/// use bobbin_bits::*;
+///
+/// FieldSet::<u32>::new().with::<Field<0, U1, RO>>(U1::B1);
+/// ```
+pub struct FieldSet<W: Word> {
+    mask: u64,
+    value: u64,
+    _word: PhantomData<W>,
+}
+
+impl<W: Word> FieldSet<W> {
+    /// Constructs an empty set.
+    #[inline]
+    pub const fn new() -> Self {
+        FieldSet { mask: 0, value: 0, _word: PhantomData }
+    }
+
+    /// Adds the assignment of `value` to the field `F`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `F` overlaps a field already in the set and assigns different values to
+    /// the shared bits.
+    #[inline]
+    pub fn with<F>(self, value: F::Value) -> Self
+    where
+        F: BitRange,
+        F::Access: Writable,
+    {
+        let bits = F::write(W::ZERO, value).to_u64();
+        let shared = self.mask & F::MASK;
+        assert!((self.value ^ bits) & shared == 0, "conflicting field assignment");
+        FieldSet {
+            mask: self.mask | F::MASK,
+            value: self.value | bits,
+            _word: PhantomData,
+        }
+    }
+
+    /// Returns the mask of all assigned bits.
+    #[inline]
+    pub fn mask(&self) -> W {
+        W::from_u64_truncate(self.mask)
+    }
+
+    /// Returns the assigned bits, with all other bits clear.
+    #[inline]
+    pub fn value(&self) -> W {
+        W::from_u64_truncate(self.value)
+    }
+
+    /// Returns a copy of `word` with all of the assignments applied.
+    #[inline]
+    pub fn apply(&self, word: W) -> W {
+        W::from_u64_truncate((word.to_u64() & !self.mask) | self.value)
+    }
+}
+
+impl<W: Word> Default for FieldSet<W> {
+    fn default() -> Self {
+        FieldSet::new()
+    }
+}
+
+impl<W: Word> Clone for FieldSet<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W: Word> Copy for FieldSet<W> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use field::Field;
+    use {U1, U4, U8};
+
+    type A = Field<0, U4>;
+    type B = Field<4, U4>;
+    type AB = Field<0, U8>;
+
+    #[test]
+    fn test_field_set() {
+        let set = FieldSet::<u16>::new().with::<B>(U4::B1010).with::<Field<15, U1>>(U1::B1);
+        assert_eq!(set.mask(), 0x80f0);
+        assert_eq!(set.value(), 0x80a0);
+        assert_eq!(set.apply(0x0000), 0x80a0);
+        assert_eq!(set.apply(0x7f0f), 0xffaf);
+        assert_eq!(FieldSet::<u8>::default().apply(0x5a), 0x5a);
+    }
+
+    #[test]
+    fn test_field_set_overlap() {
+        // Overlapping assignments that agree are allowed
+        let set = FieldSet::<u8>::new().with::<A>(U4::B0011).with::<AB>(U8::from(0x53u8));
+        assert_eq!(set.value(), 0x53);
+        let set = set.with::<B>(U4::B0101);
+        assert_eq!(set.value(), 0x53);
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicting field assignment")]
+    fn test_field_set_conflict() {
+        FieldSet::<u8>::new().with::<AB>(U8::from(0x53u8)).with::<B>(U4::B0100);
+    }
+}
@@ -45,7 +45,7 @@
 //!```
 //!
 //!
-//!Similarly, R1 through R32 are enums with repr(usize). Their members are named with
+//!Similarly, R1 through R33 are enums with repr(usize). Their members are named with
 //!the prefix "X" followed by the hexadecimal represention of the number, single digits
 //!for 0-15 and two digits for 16-32:
 //!
@@ -70,6 +70,7 @@
 //!Types U7 and U8, U9 to U16 and U16 through U32 are wrappers around u8, u16 and u32 respectively:
 //!
 //!```
+//!#[repr(transparent)]
 //!pub struct U20(u16);
 //!```
 //!
@@ -100,6 +101,28 @@
 //!- `From<bool> for U1`
 //!- `Not for U1`
 //!
+//!## Overview
+//!
+//!Beyond the types themselves, the crate provides:
+//!
+//!- [`FieldExt`] and [`Field`] for U-typed fields within u8 to u64 words, and [`Scattered`] for fields split across several bit ranges
+//!- [`ByteFieldExt`] for fields at any bit offset of a byte buffer, in a selectable [`BitOrder`]
+//!- [`BitReader`] and [`BitWriter`] for bit streams, with the [`parse`], [`uper`] and [`codes`] modules built on them
+//!- [`PackedArray`] and `PackedVec` for U values stored in exactly their width, and the [`bitpack`] module for blocks of u32 values
+//!- [`pack_raw10`] and [`unpack_raw10`] for MIPI CSI-2 RAW10, with matching functions for RAW12, RAW14 and P010
+//!- `try_cast_slice` and `from_le_bytes` on the U and R types, reporting a [`RangeError`]
+//!- [`Register`], [`FieldSet`] and field access policies for memory-mapped registers, and `SimBus` for testing them on the host
+//!- [`AtomicFields`] and `AtomicU1` through `AtomicU31` for atomic field updates
+//!- [`bit_band_alias`] and [`BitBandBit`] for Cortex-M3/M4 bit-banding on 32- and 64-bit targets
+//!
+//!`PackedVec`, `VecBitWriter` and `SimBus` require the `alloc` feature. With the `derive` feature,
+//!`#[derive(BitStruct)]` and `#[derive(BitEnum)]` from the bobbin-bits-derive crate pack structs of
+//!U-typed fields and map enums onto U types.
+//!
+//!The bobbin-bits-gen crate generates register definitions from CMSIS-SVD files and from a small
+//!layout format, along with C headers, and its `bobbin-bits` tool decodes, encodes and diffs values
+//!field by field.
+//!
 //!## Examples
 //!
 //!Here's an example using the U4 bit field type:
@@ -148,11 +171,9 @@
 //!assert_eq!(c, '8');
 //!
 //!// A function that will extract bits [4:7] from a u32 value
-//!// without range checking
+//!// without range checking. The offset is checked at compile time.
 //!fn extract_u4(v: u32) -> U4 {
-//!    unsafe {
-//!        U4::from_u32_unchecked(v >> 4 & 0b1111)
-//!    }
+//!    v.get_field_at::<4, U4>()
 //!}
 //!
 //!// No range checking needs to take place if a U4 is used
@@ -227,6 +248,15 @@
 #[cfg(test)]
 #[macro_use] extern crate std;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "derive")]
+extern crate bobbin_bits_derive;
+
+#[cfg(feature = "derive")]
+pub use bobbin_bits_derive::{BitEnum, BitStruct};
+
 use core::fmt;
 use core::mem::transmute;
 use core::ops::Not;
@@ -235,6 +265,42 @@ use core::ops::Not;
 #[macro_use] mod structs;
 #[macro_use] mod ranges;
 
+mod access;
+#[cfg(target_has_atomic = "32")]
+mod atomic;
+#[cfg(any(target_pointer_width = "32", target_pointer_width = "64"))]
+mod bitband;
+pub mod bitpack;
+mod bits;
+mod bytes;
+pub mod codes;
+mod field;
+mod field_set;
+mod packed;
+pub mod parse;
+mod pixel;
+mod register;
+#[cfg(feature = "alloc")]
+mod sim;
+mod stream;
+pub mod uper;
+
+pub use access::*;
+#[cfg(target_has_atomic = "32")]
+pub use atomic::*;
+#[cfg(any(target_pointer_width = "32", target_pointer_width = "64"))]
+pub use bitband::*;
+pub use bits::*;
+pub use bytes::*;
+pub use field::*;
+pub use field_set::*;
+pub use packed::*;
+pub use pixel::*;
+pub use register::*;
+#[cfg(feature = "alloc")]
+pub use sim::*;
+pub use stream::*;
+
 // Generate enums from U1 to U6
 
 impl_enum!(U1, 1, 0b1, 
@@ -383,34 +449,34 @@ impl_enum!(U6, 6, 0b111111,
 
 // Generate structs from U7 to U32
 
-impl_u8!(U7, 0b111_1111);
-impl_u8!(U8, 0b1111_11111);
-impl_u16!(U9, 0b1_1111_1111);
-impl_u16!(U10, 0b11_1111_1111);
-impl_u16!(U11, 0b111_1111_1111);
-impl_u16!(U12, 0b1111_1111_1111);
-impl_u16!(U13, 0b1_1111_1111_1111);
-impl_u16!(U14, 0b11_1111_1111_1111);
-impl_u16!(U15, 0b111_1111_1111_1111);
-impl_u16!(U16, 0b1111_1111_1111_1111);
-impl_u32!(U17, 0b1_1111_1111_1111_1111);
-impl_u32!(U18, 0b11_1111_1111_1111_1111);
-impl_u32!(U19, 0b111_1111_1111_1111_1111);
-impl_u32!(U20, 0b1111_1111_1111_1111_1111);
-impl_u32!(U21, 0b1_1111_1111_1111_1111_1111);
-impl_u32!(U22, 0b11_1111_1111_1111_1111_1111);
-impl_u32!(U23, 0b111_1111_1111_1111_1111_1111);
-impl_u32!(U24, 0b1111_1111_1111_1111_1111_1111);
-impl_u32!(U25, 0b1_1111_1111_1111_1111_1111_1111);
-impl_u32!(U26, 0b11_1111_1111_1111_1111_1111_1111);
-impl_u32!(U27, 0b111_1111_1111_1111_1111_1111_1111);
-impl_u32!(U28, 0b1111_1111_1111_1111_1111_1111_1111);
-impl_u32!(U29, 0b1_1111_1111_1111_1111_1111_1111_1111);
-impl_u32!(U30, 0b11_1111_1111_1111_1111_1111_1111_1111);
-impl_u32!(U31, 0b111_1111_1111_1111_1111_1111_1111_1111);
-impl_u32!(U32, 0b1111_1111_1111_1111_1111_1111_1111_1111);
-
-// Generate ranges from R1 to R32
+impl_u8!(U7, 7, 0b111_1111);
+impl_u8!(U8, 8, 0b1111_1111);
+impl_u16!(U9, 9, 0b1_1111_1111);
+impl_u16!(U10, 10, 0b11_1111_1111);
+impl_u16!(U11, 11, 0b111_1111_1111);
+impl_u16!(U12, 12, 0b1111_1111_1111);
+impl_u16!(U13, 13, 0b1_1111_1111_1111);
+impl_u16!(U14, 14, 0b11_1111_1111_1111);
+impl_u16!(U15, 15, 0b111_1111_1111_1111);
+impl_u16!(U16, 16, 0b1111_1111_1111_1111);
+impl_u32!(U17, 17, 0b1_1111_1111_1111_1111);
+impl_u32!(U18, 18, 0b11_1111_1111_1111_1111);
+impl_u32!(U19, 19, 0b111_1111_1111_1111_1111);
+impl_u32!(U20, 20, 0b1111_1111_1111_1111_1111);
+impl_u32!(U21, 21, 0b1_1111_1111_1111_1111_1111);
+impl_u32!(U22, 22, 0b11_1111_1111_1111_1111_1111);
+impl_u32!(U23, 23, 0b111_1111_1111_1111_1111_1111);
+impl_u32!(U24, 24, 0b1111_1111_1111_1111_1111_1111);
+impl_u32!(U25, 25, 0b1_1111_1111_1111_1111_1111_1111);
+impl_u32!(U26, 26, 0b11_1111_1111_1111_1111_1111_1111);
+impl_u32!(U27, 27, 0b111_1111_1111_1111_1111_1111_1111);
+impl_u32!(U28, 28, 0b1111_1111_1111_1111_1111_1111_1111);
+impl_u32!(U29, 29, 0b1_1111_1111_1111_1111_1111_1111_1111);
+impl_u32!(U30, 30, 0b11_1111_1111_1111_1111_1111_1111_1111);
+impl_u32!(U31, 31, 0b111_1111_1111_1111_1111_1111_1111_1111);
+impl_u32!(U32, 32, 0b1111_1111_1111_1111_1111_1111_1111_1111);
+
+// Generate ranges from R1 to R33
 
 impl_range!(R1, 1,
     X0 = 0x0,
@@ -1099,6 +1165,44 @@ impl_range!(R32, 32,
 
 impl_range_from!(R32, R31, R30, R29, R28, R27, R26, R25, R24, R23, R22, R20, R19, R18, R17, R16, R15, R14, R13, R12, R11, R10, R9, R8, R7, R5, R4, R3, R2, R1);
 
+impl_range!(R33, 33,
+    X00 = 0x00,
+    X01 = 0x01,
+    X02 = 0x02,
+    X03 = 0x03,
+    X04 = 0x04,
+    X05 = 0x05,
+    X06 = 0x06,
+    X07 = 0x07,
+    X08 = 0x08,
+    X09 = 0x09,
+    X0a = 0x0a,
+    X0b = 0x0b,
+    X0c = 0x0c,
+    X0d = 0x0d,
+    X0e = 0x0e,
+    X0f = 0x0f,
+    X10 = 0x10,
+    X11 = 0x11,
+    X12 = 0x12,
+    X13 = 0x13,
+    X14 = 0x14,
+    X15 = 0x15,
+    X16 = 0x16,
+    X17 = 0x17,
+    X18 = 0x18,
+    X19 = 0x19,
+    X1a = 0x1a,
+    X1b = 0x1b,
+    X1c = 0x1c,
+    X1d = 0x1d,
+    X1e = 0x1e,
+    X1f = 0x1f,
+    X20 = 0x20,
+);
+
+impl_range_from!(R33, R32, R31, R30, R29, R28, R27, R26, R25, R24, R23, R22, R20, R19, R18, R17, R16, R15, R14, R13, R12, R11, R10, R9, R8, R7, R5, R4, R3, R2, R1);
+
 
 // Special Traits for U1
 
@@ -1141,6 +1245,7 @@ mod tests {
         // let value: u32 = U1::B1.into();
     }
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_bool_cast() {
         assert_eq!(true as u32, 1);
         assert_eq!(false as u32, 0);
@@ -1289,4 +1394,54 @@ mod tests {
         assert_eq!(format!("{}",U28::from(0xfffffffu32)),"268435455");
         assert_eq!(format!("{}",U32::from(0xffffffffu32)),"4294967295");
     }
+
+    #[test]
+    fn test_cast_slice() {
+        let mut bytes = [0u8, 5, 7, 3];
+        assert_eq!(U3::try_cast_slice(&bytes), Ok(&[U3::B000, U3::B101, U3::B111, U3::B011][..]));
+        U3::try_cast_slice_mut(&mut bytes).unwrap()[0] = U3::B110;
+        assert_eq!(bytes[0], 6);
+        bytes[3] = 8;
+        assert_eq!(U3::try_cast_slice(&bytes), Err(RangeError { index: 3 }));
+
+        assert_eq!(U8::try_cast_slice(&[0xff, 0x00]).unwrap()[0], U8::from(0xffu8));
+        assert_eq!(U7::try_cast_slice(&[0x7f, 0x80]), Err(RangeError { index: 1 }));
+        assert_eq!(U32::as_primitive_slice(U32::try_cast_slice(&[u32::MAX]).unwrap()), [u32::MAX]);
+        assert_eq!(U20::try_cast_slice(&[0xf_ffff, 0x10_0000]), Err(RangeError { index: 1 }));
+
+        let mut words = [31usize, 0, 32];
+        assert_eq!(R32::try_cast_slice(&words), Err(RangeError { index: 2 }));
+        words[2] = 17;
+        let r = R32::try_cast_slice_mut(&mut words).unwrap();
+        r[1] = R32::X1f;
+        assert_eq!(R32::as_primitive_slice(r), [31, 31, 17]);
+        assert_eq!(format!("{}", RangeError { index: 2 }), "value at index 2 is out of range");
+    }
+
+    #[test]
+    fn test_bytes() {
+        let v = U24::from(0x12_3456u32);
+        assert_eq!(v.to_le_bytes(), [0x56, 0x34, 0x12]);
+        assert_eq!(v.to_be_bytes(), [0x12, 0x34, 0x56]);
+        assert_eq!(U24::from_le_bytes([0x56, 0x34, 0x12]), Ok(v));
+        assert_eq!(U24::from_be_bytes([0x12, 0x34, 0x56]), Ok(v));
+
+        let v = U12::from(0xabcu16);
+        assert_eq!(v.to_le_bytes(), [0xbc, 0x0a]);
+        assert_eq!(v.to_be_bytes(), [0x0a, 0xbc]);
+        assert_eq!(U12::from_be_bytes([0x0a, 0xbc]), Ok(v));
+        // The padding bits above bit 11 must be clear.
+        assert_eq!(U12::from_be_bytes([0x1a, 0xbc]), Err(RangeError { index: 0 }));
+        assert_eq!(U12::from_le_bytes([0xbc, 0x8a]), Err(RangeError { index: 0 }));
+
+        assert_eq!(U5::B10101.to_le_bytes(), [0x15]);
+        assert_eq!(U5::from_be_bytes([0x15]), Ok(U5::B10101));
+        assert_eq!(U5::from_le_bytes([0x20]), Err(RangeError { index: 0 }));
+        assert_eq!(U7::from_le_bytes([0x80]), Err(RangeError { index: 0 }));
+        assert_eq!(U8::from_le_bytes([0xff]), Ok(U8::from(0xffu8)));
+        assert_eq!(U17::from(0x1_0000u32).to_be_bytes(), [0x01, 0x00, 0x00]);
+        assert_eq!(U17::from_le_bytes([0, 0, 2]), Err(RangeError { index: 0 }));
+        assert_eq!(U32::from(0x0102_0304u32).to_le_bytes(), [4, 3, 2, 1]);
+        assert_eq!(U32::from_be_bytes([0xff; 4]), Ok(U32::from(u32::MAX)));
+    }
 }
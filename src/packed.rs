@@ -0,0 +1,682 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+
+use bits::BitWidth;
+
+/// Returns the number of u64 words needed to pack `len` values of `width` bits.
+///
+/// This is the `WORDS` parameter of a `PackedArray`.
+#[inline]
+pub const fn packed_words(width: u32, len: usize) -> usize {
+    (len * width as usize).div_ceil(64)
+}
+
+/// Returns the mask covering the low `width` bits, for `width` up to 64.
+#[inline]
+fn mask(width: u32) -> u64 {
+    if width == 0 { 0 } else { u64::MAX >> (64 - width) }
+}
+
+/// Returns the `width` bits at bit `pos` of `words`, for `width` up to 64.
+#[inline]
+pub(crate) fn read_bits(words: &[u64], pos: usize, width: u32) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let (i, off) = (pos / 64, (pos % 64) as u32);
+    let mut bits = words[i] >> off;
+    if off + width > 64 {
+        bits |= words[i + 1] << (64 - off);
+    }
+    bits & mask(width)
+}
+
+/// Replaces the `width` bits at bit `pos` of `words` with the low bits of `value`, for
+/// `width` up to 64.
+#[inline]
+fn write_bits(words: &mut [u64], pos: usize, width: u32, value: u64) {
+    if width == 0 {
+        return;
+    }
+    let (i, off) = (pos / 64, (pos % 64) as u32);
+    let (m, value) = (mask(width), value & mask(width));
+    words[i] = (words[i] & !(m << off)) | (value << off);
+    if off + width > 64 {
+        let shift = 64 - off;
+        words[i + 1] = (words[i + 1] & !(m >> shift)) | (value >> shift);
+    }
+}
+
+/// Copies `len` bits from bit `src_pos` of `src` to bit `dst_pos` of `dst`, a word at a time.
+fn copy_bits(dst: &mut [u64], dst_pos: usize, src: &[u64], src_pos: usize, len: usize) {
+    let mut done = 0;
+    while done < len {
+        let n = (len - done).min(64) as u32;
+        write_bits(dst, dst_pos + done, n, read_bits(src, src_pos + done, n));
+        done += n as usize;
+    }
+}
+
+/// Fills the first `len` values of `words` with `value`, leaving the bits after them clear.
+fn fill_bits(words: &mut [u64], len: usize, width: u32, value: u64) {
+    let bits = len * width as usize;
+    let n = bits.div_ceil(64);
+    for (k, word) in words[..n].iter_mut().enumerate() {
+        // The value repeats every `width` bits, so each word is the same pattern at a
+        // different phase.
+        let phase = ((k * 64) % width as usize) as u32;
+        let mut w = value >> phase;
+        let mut shift = width - phase;
+        while shift < 64 {
+            w |= value << shift;
+            shift += width;
+        }
+        *word = w;
+    }
+    if bits % 64 != 0 {
+        words[n - 1] &= mask((bits % 64) as u32);
+    }
+}
+
+/// Converts `range` into start and end indices, panicking if it is out of bounds.
+#[inline]
+fn bounds<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n + 1,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end && end <= len, "range out of bounds");
+    (start, end)
+}
+
+/// A view of a run of values in a `PackedArray` or `PackedVec`.
+pub struct PackedSlice<'a, T: BitWidth> {
+    words: &'a [u64],
+    start: usize,
+    len: usize,
+    _t: PhantomData<T>,
+}
+
+impl<'a, T: BitWidth> Clone for PackedSlice<'a, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: BitWidth> Copy for PackedSlice<'a, T> {}
+
+impl<'a, T: BitWidth> PackedSlice<'a, T> {
+    #[inline]
+    fn new(words: &'a [u64], start: usize, len: usize) -> Self {
+        PackedSlice { words, start, len, _t: PhantomData }
+    }
+
+    /// Returns the number of values in the slice.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the slice is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len {
+            let pos = (self.start + index) * T::WIDTH as usize;
+            Some(T::from_bits_truncate(read_bits(self.words, pos, T::WIDTH) as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the values in `range` as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedSlice<'a, T> {
+        let (start, end) = bounds(range, self.len);
+        PackedSlice::new(self.words, self.start + start, end - start)
+    }
+
+    /// Returns an iterator over the values.
+    #[inline]
+    pub fn iter(&self) -> PackedIter<'a, T> {
+        PackedIter { slice: *self, front: 0, back: self.len }
+    }
+
+    /// Returns the position of the first bit of the slice in the underlying words.
+    #[inline]
+    fn pos(&self) -> usize {
+        self.start * T::WIDTH as usize
+    }
+}
+
+impl<'a, T: BitWidth + fmt::Debug> fmt::Debug for PackedSlice<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, 'b, T: BitWidth> PartialEq<PackedSlice<'b, T>> for PackedSlice<'a, T> {
+    fn eq(&self, other: &PackedSlice<'b, T>) -> bool {
+        self.len == other.len && self.iter().zip(other.iter()).all(|(a, b)| a.bits() == b.bits())
+    }
+}
+
+impl<'a, T: BitWidth> IntoIterator for PackedSlice<'a, T> {
+    type Item = T;
+    type IntoIter = PackedIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> PackedIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// An iterator over the values of a `PackedSlice`.
+#[derive(Clone)]
+pub struct PackedIter<'a, T: BitWidth> {
+    slice: PackedSlice<'a, T>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T: BitWidth> Iterator for PackedIter<'a, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.front < self.back {
+            self.front += 1;
+            self.slice.get(self.front - 1)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+}
+
+impl<'a, T: BitWidth> DoubleEndedIterator for PackedIter<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.front < self.back {
+            self.back -= 1;
+            self.slice.get(self.back)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: BitWidth> ExactSizeIterator for PackedIter<'a, T> {}
+
+/// A fixed-size array of `N` values of type `T`, each stored in exactly `T::WIDTH` bits.
+///
+/// Values are packed into `WORDS` u64 words, value `i` at bits `i * T::WIDTH` onwards, so
+/// 1000 `U3` values take 47 words rather than 1000 bytes. Values may straddle word
+/// boundaries. Stable Rust cannot compute the number of words from `T` and `N`, so it is
+/// given explicitly, usually as `{ packed_words(T::WIDTH, N) }`. It is checked at compile
+/// time.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut a: PackedArray<U3, 1000, { packed_words(U3::WIDTH, 1000) }> = PackedArray::new();
+/// assert_eq!(core::mem::size_of_val(&a), 376);
+///
+/// a.fill(U3::B101);
+/// a.set(21, U3::B010);
+/// assert_eq!(a.get(20), Some(U3::B101));
+/// assert_eq!(a.get(21), Some(U3::B010));
+/// assert_eq!(a.get(1000), None);
+/// assert_eq!(a.iter().filter(|&v| v == U3::B010).count(), 1);
+///
+/// let s = a.slice(20..23);
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [U3::B101, U3::B010, U3::B101]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PackedArray<T: BitWidth, const N: usize, const WORDS: usize> {
+    words: [u64; WORDS],
+    _t: PhantomData<T>,
+}
+
+impl<T: BitWidth, const N: usize, const WORDS: usize> PackedArray<T, N, WORDS> {
+    /// Constructs an array with every value zero.
+    ///
+    /// `WORDS` must be large enough to hold the values:
+    ///
+    /// ```compile_fail
+    /// use bobbin_bits::*;
+    ///
+    /// let a: PackedArray<U3, 22, 1> = PackedArray::new();
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        const { assert!(WORDS >= packed_words(T::WIDTH, N), "packed array storage is too small") };
+        PackedArray { words: [0; WORDS], _t: PhantomData }
+    }
+
+    /// Returns the number of values in the array, `N`.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if the array is empty.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns the packed words of the array. Bits after the last value are clear.
+    #[inline]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the whole array as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> PackedSlice<'_, T> {
+        PackedSlice::new(&self.words, 0, N)
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.as_slice().get(index)
+    }
+
+    /// Replaces the value at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < N, "index out of bounds");
+        write_bits(&mut self.words, index * T::WIDTH as usize, T::WIDTH, value.bits() as u64);
+    }
+
+    /// Returns the values in `range` as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedSlice<'_, T> {
+        self.as_slice().slice(range)
+    }
+
+    /// Returns an iterator over the values.
+    #[inline]
+    pub fn iter(&self) -> PackedIter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Sets every value to `value`.
+    #[inline]
+    pub fn fill(&mut self, value: T) {
+        fill_bits(&mut self.words, N, T::WIDTH, value.bits() as u64);
+    }
+
+    /// Copies the values of `src` into the array, starting at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` does not fit in the array at `index`.
+    #[inline]
+    pub fn copy_from(&mut self, index: usize, src: PackedSlice<T>) {
+        assert!(index <= N && src.len() <= N - index, "range out of bounds");
+        let width = T::WIDTH as usize;
+        copy_bits(&mut self.words, index * width, src.words, src.pos(), src.len() * width);
+    }
+}
+
+impl<T: BitWidth, const N: usize, const WORDS: usize> Default for PackedArray<T, N, WORDS> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: BitWidth + fmt::Debug, const N: usize, const WORDS: usize> fmt::Debug for PackedArray<T, N, WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<'a, T: BitWidth, const N: usize, const WORDS: usize> IntoIterator for &'a PackedArray<T, N, WORDS> {
+    type Item = T;
+    type IntoIter = PackedIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> PackedIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// A growable vector of values of type `T`, each stored in exactly `T::WIDTH` bits.
+///
+/// Values are laid out as in a `PackedArray`, and `PackedSlice` views can be copied
+/// between the two.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut v: PackedVec<U5> = (0..10u8).map(U5::from).collect();
+/// v.push(U5::from(31u8));
+/// assert_eq!(v.len(), 11);
+/// assert_eq!(v.as_words().len(), 1);
+/// assert_eq!(v.get(10), Some(U5::from(31u8)));
+///
+/// let mut w = PackedVec::new();
+/// w.extend_from_slice(v.slice(8..));
+/// assert_eq!(w.iter().map(|v| v.value()).collect::<Vec<_>>(), [8, 9, 31]);
+/// assert_eq!(w.pop(), Some(U5::from(31u8)));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct PackedVec<T: BitWidth> {
+    words: Vec<u64>,
+    len: usize,
+    _t: PhantomData<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: BitWidth> PackedVec<T> {
+    /// Constructs an empty vector.
+    #[inline]
+    pub const fn new() -> Self {
+        PackedVec { words: Vec::new(), len: 0, _t: PhantomData }
+    }
+
+    /// Constructs an empty vector with room for `capacity` values.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        PackedVec { words: Vec::with_capacity(packed_words(T::WIDTH, capacity)), len: 0, _t: PhantomData }
+    }
+
+    /// Returns the number of values in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the vector is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the packed words of the vector. Bits after the last value are clear.
+    #[inline]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the whole vector as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> PackedSlice<'_, T> {
+        PackedSlice::new(&self.words, 0, self.len)
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.as_slice().get(index)
+    }
+
+    /// Replaces the value at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < self.len, "index out of bounds");
+        write_bits(&mut self.words, index * T::WIDTH as usize, T::WIDTH, value.bits() as u64);
+    }
+
+    /// Returns the values in `range` as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedSlice<'_, T> {
+        self.as_slice().slice(range)
+    }
+
+    /// Returns an iterator over the values.
+    #[inline]
+    pub fn iter(&self) -> PackedIter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Resizes the storage for `len` values. Values after `len` must already be clear.
+    #[inline]
+    fn resize(&mut self, len: usize) {
+        self.words.resize(packed_words(T::WIDTH, len), 0);
+        self.len = len;
+    }
+
+    /// Appends `value`.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        let index = self.len;
+        self.resize(index + 1);
+        self.set(index, value);
+    }
+
+    /// Removes and returns the last value, or `None` if the vector is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.get(self.len.checked_sub(1)?)?;
+        self.truncate(self.len - 1);
+        Some(value)
+    }
+
+    /// Shortens the vector to `len` values, doing nothing if it is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let pos = len * T::WIDTH as usize;
+            if pos % 64 != 0 {
+                self.words[pos / 64] &= mask((pos % 64) as u32);
+            }
+            self.resize(len);
+        }
+    }
+
+    /// Removes every value.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Sets every value to `value`.
+    #[inline]
+    pub fn fill(&mut self, value: T) {
+        fill_bits(&mut self.words, self.len, T::WIDTH, value.bits() as u64);
+    }
+
+    /// Copies the values of `src` into the vector, starting at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` does not fit in the vector at `index`.
+    #[inline]
+    pub fn copy_from(&mut self, index: usize, src: PackedSlice<T>) {
+        assert!(index <= self.len && src.len() <= self.len - index, "range out of bounds");
+        let width = T::WIDTH as usize;
+        copy_bits(&mut self.words, index * width, src.words, src.pos(), src.len() * width);
+    }
+
+    /// Appends the values of `src`.
+    #[inline]
+    pub fn extend_from_slice(&mut self, src: PackedSlice<T>) {
+        let index = self.len;
+        self.resize(index + src.len());
+        self.copy_from(index, src);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: BitWidth> Default for PackedVec<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: BitWidth + fmt::Debug> fmt::Debug for PackedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: BitWidth> Extend<T> for PackedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: BitWidth> FromIterator<T> for PackedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = PackedVec::new();
+        v.extend(iter);
+        v
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: BitWidth> IntoIterator for &'a PackedVec<T> {
+    type Item = T;
+    type IntoIter = PackedIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> PackedIter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {U3, U7, U32};
+
+    #[test]
+    fn test_array() {
+        let mut a: PackedArray<U7, 20, { packed_words(7, 20) }> = PackedArray::new();
+        assert_eq!(a.as_words().len(), 3);
+        for i in 0..20 {
+            a.set(i, U7::from(i as u8 * 5));
+        }
+        for i in 0..20 {
+            assert_eq!(a.get(i), Some(U7::from(i as u8 * 5)));
+        }
+        // The value at index 9 straddles the first and second words.
+        assert_eq!(a.as_words()[0] >> 63, 45 & 1);
+        assert_eq!(a.as_words()[2] >> (140 - 128), 0);
+        assert_eq!(a.iter().next_back(), Some(U7::from(95u8)));
+        assert_eq!(a.iter().len(), 20);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut a: PackedArray<U3, 100, { packed_words(3, 100) }> = PackedArray::new();
+        a.fill(U3::B011);
+        assert!(a.iter().all(|x| x == U3::B011));
+        // Bits after the last value stay clear.
+        assert_eq!(a.as_words()[4] >> (300 % 64), 0);
+
+        let mut a: PackedArray<U32, 3, 3> = PackedArray::new();
+        a.fill(U32::from(0xdead_beefu32));
+        assert_eq!(a.as_words(), [0xdead_beef_dead_beef, 0xdead_beef, 0]);
+    }
+
+    #[test]
+    fn test_copy() {
+        let mut src: PackedArray<U7, 200, { packed_words(7, 200) }> = PackedArray::new();
+        for i in 0..200 {
+            src.set(i, U7::from((i % 128) as u8));
+        }
+        let mut dst: PackedArray<U7, 100, { packed_words(7, 100) }> = PackedArray::new();
+        dst.fill(U7::from(0x7fu8));
+        dst.copy_from(3, src.slice(50..140));
+        assert_eq!(dst.get(2), Some(U7::from(0x7fu8)));
+        assert_eq!(dst.slice(3..93), src.slice(50..140));
+        assert_eq!(dst.get(93), Some(U7::from(0x7fu8)));
+        assert_eq!(std::format!("{:?}", dst.slice(92..94)), "[0x0b, 0x7f]");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_vec() {
+        use U1;
+
+        let mut v: PackedVec<U1> = (0..130).map(|i| U1::from(i % 3 == 0)).collect();
+        assert_eq!(v.as_words().len(), 3);
+        v.truncate(64);
+        assert_eq!(v.as_words(), [0x9249_2492_4924_9249]);
+        v.truncate(63);
+        assert_eq!(v.as_words(), [0x1249_2492_4924_9249]);
+        assert_eq!(v.pop(), Some(U1::B0));
+        v.clear();
+        assert_eq!(v, PackedVec::new());
+        assert_eq!(v.pop(), None);
+
+        for &len in &[0, 1, 21, 22, 64, 65] {
+            let mut v: PackedVec<U3> = (0..len).map(|_| U3::B000).collect();
+            v.fill(U3::B110);
+            assert!(v.iter().all(|x| x == U3::B110));
+            v.push(U3::B001);
+            assert_eq!(v.get(len), Some(U3::B001));
+            v.extend_from_slice(v.clone().slice(..));
+            assert_eq!(v.len(), 2 * len + 2);
+            assert_eq!(v.slice(len + 1..), v.slice(..len + 1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_set_out_of_bounds() {
+        let mut a: PackedArray<U3, 5, 1> = PackedArray::new();
+        a.set(5, U3::B000);
+    }
+
+    #[test]
+    #[should_panic(expected = "range out of bounds")]
+    fn test_slice_out_of_bounds() {
+        let a: PackedArray<U3, 5, 1> = PackedArray::new();
+        a.slice(2..6);
+    }
+}
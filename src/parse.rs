@@ -0,0 +1,295 @@
+//! Bit-level parser combinators.
+//!
+//! A parser is any `FnMut(&mut BitReader) -> Result<O, ParseError>`. The combinators in
+//! this module build parsers from U types and other parsers, so that bit-packed headers
+//! can be parsed declaratively. A parser that fails leaves the reader where it was, and
+//! the error records the position in bits at which parsing failed.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits::parse::*;
+//! use std::convert::TryFrom;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Kind { Data, Ack }
+//!
+//! impl TryFrom<U2> for Kind {
+//!     type Error = ();
+//!     fn try_from(v: U2) -> Result<Self, ()> {
+//!         match v {
+//!             U2::B00 => Ok(Kind::Data),
+//!             U2::B01 => Ok(Kind::Ack),
+//!             _ => Err(()),
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Header { kind: Kind, ext: Option<U8>, lanes: [U3; 2] }
+//!
+//! fn header(r: &mut BitReader) -> Result<Header, ParseError> {
+//!     let (_, kind, has_ext) = tuple((tag(U3::B101), map_res(bits::<U2>(), Kind::try_from), bits::<U1>()))(r)?;
+//!     let ext = cond(has_ext == U1::B1, bits::<U8>())(r)?;
+//!     let lanes = count::<_, _, 2>(bits::<U3>())(r)?;
+//!     Ok(Header { kind, ext, lanes })
+//! }
+//!
+//! let mut r = BitReader::new(&[0b101_01_1_00, 0b1111_11_01, 0b0_0000000], StreamOrder::MsbFirst);
+//! assert_eq!(header(&mut r), Ok(Header { kind: Kind::Ack, ext: Some(U8::from(0x3fu8)), lanes: [U3::B010, U3::B000] }));
+//!
+//! let mut r = BitReader::new(&[0b101_11_0_00], StreamOrder::MsbFirst);
+//! assert_eq!(header(&mut r), Err(ParseError { position: 3, kind: ParseErrorKind::MapRes }));
+//! assert_eq!(r.position(), 0);
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+use bits::BitWidth;
+use stream::{BitReader, StreamError};
+
+/// The reason a parser failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input ended before the parser finished.
+    Underrun,
+    /// A value did not match the expected tag.
+    Tag,
+    /// A value was rejected by the conversion in `map_res`.
+    MapRes,
+}
+
+/// An error returned by a parser, with the position in bits at which it occurred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The position in bits of the value that could not be parsed.
+    pub position: usize,
+    /// The reason parsing failed.
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Constructs an error at the current position of `input`.
+    #[inline]
+    pub fn new(input: &BitReader, kind: ParseErrorKind) -> Self {
+        ParseError { position: input.position(), kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self.kind {
+            ParseErrorKind::Underrun => "unexpected end of input",
+            ParseErrorKind::Tag => "tag mismatch",
+            ParseErrorKind::MapRes => "invalid value",
+        };
+        write!(f, "{} at bit {}", what, self.position)
+    }
+}
+
+/// A parser producing values of type `O`.
+///
+/// Implemented for every `FnMut(&mut BitReader) -> Result<O, ParseError>`.
+pub trait Parser<'a, O> {
+    /// Parses a value from `input`.
+    fn parse(&mut self, input: &mut BitReader<'a>) -> Result<O, ParseError>;
+}
+
+impl<'a, O, F> Parser<'a, O> for F
+where
+    F: FnMut(&mut BitReader<'a>) -> Result<O, ParseError>,
+{
+    #[inline]
+    fn parse(&mut self, input: &mut BitReader<'a>) -> Result<O, ParseError> {
+        self(input)
+    }
+}
+
+/// Runs `f` on `input`, restoring the position of `input` if it fails.
+#[inline]
+fn restore<'a, O, F>(input: &mut BitReader<'a>, f: F) -> Result<O, ParseError>
+where
+    F: FnOnce(&mut BitReader<'a>) -> Result<O, ParseError>,
+{
+    let start = input.clone();
+    f(input).inspect_err(|_| *input = start)
+}
+
+/// Returns a parser that reads a value of type `T`.
+#[inline]
+pub fn bits<'a, T: BitWidth>() -> impl FnMut(&mut BitReader<'a>) -> Result<T, ParseError> {
+    |input: &mut BitReader<'a>| {
+        input.read::<T>().map_err(|e| match e {
+            StreamError::Underrun | StreamError::Overflow => ParseError::new(input, ParseErrorKind::Underrun),
+        })
+    }
+}
+
+/// Returns a parser that reads a value of type `T` and fails unless it is `expected`.
+#[inline]
+pub fn tag<'a, T: BitWidth>(expected: T) -> impl FnMut(&mut BitReader<'a>) -> Result<T, ParseError> {
+    move |input: &mut BitReader<'a>| {
+        let start = input.clone();
+        let value = bits::<T>()(input)?;
+        if value.bits() != expected.bits() {
+            let e = ParseError::new(&start, ParseErrorKind::Tag);
+            *input = start;
+            return Err(e);
+        }
+        Ok(value)
+    }
+}
+
+/// Returns a parser that runs `parser` if `condition` is true, and otherwise produces
+/// `None` without reading anything.
+#[inline]
+pub fn cond<'a, O, P>(condition: bool, mut parser: P) -> impl FnMut(&mut BitReader<'a>) -> Result<Option<O>, ParseError>
+where
+    P: Parser<'a, O>,
+{
+    move |input: &mut BitReader<'a>| if condition { parser.parse(input).map(Some) } else { Ok(None) }
+}
+
+/// Returns a parser that runs `parser` `N` times.
+#[inline]
+pub fn count<'a, O, P, const N: usize>(mut parser: P) -> impl FnMut(&mut BitReader<'a>) -> Result<[O; N], ParseError>
+where
+    P: Parser<'a, O>,
+{
+    move |input: &mut BitReader<'a>| {
+        restore(input, |input| {
+            let mut out: [Option<O>; N] = [const { None }; N];
+            for o in out.iter_mut() {
+                *o = Some(parser.parse(input)?);
+            }
+            Ok(out.map(|o| o.unwrap()))
+        })
+    }
+}
+
+/// Returns a parser that runs `parser` `n` times, collecting the values into a `Vec`.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn count_vec<'a, O, P>(mut parser: P, n: usize) -> impl FnMut(&mut BitReader<'a>) -> Result<Vec<O>, ParseError>
+where
+    P: Parser<'a, O>,
+{
+    move |input: &mut BitReader<'a>| restore(input, |input| (0..n).map(|_| parser.parse(input)).collect())
+}
+
+/// Returns a parser that applies `f` to the value produced by `parser`.
+#[inline]
+pub fn map<'a, O1, O2, P, F>(mut parser: P, mut f: F) -> impl FnMut(&mut BitReader<'a>) -> Result<O2, ParseError>
+where
+    P: Parser<'a, O1>,
+    F: FnMut(O1) -> O2,
+{
+    move |input: &mut BitReader<'a>| parser.parse(input).map(&mut f)
+}
+
+/// Returns a parser that applies the fallible conversion `f`, such as `TryFrom::try_from`,
+/// to the value produced by `parser`.
+///
+/// A conversion error is reported at the position of the value.
+#[inline]
+pub fn map_res<'a, O1, O2, E, P, F>(mut parser: P, mut f: F) -> impl FnMut(&mut BitReader<'a>) -> Result<O2, ParseError>
+where
+    P: Parser<'a, O1>,
+    F: FnMut(O1) -> Result<O2, E>,
+{
+    move |input: &mut BitReader<'a>| {
+        let start = input.clone();
+        let value = parser.parse(input)?;
+        f(value).map_err(|_| {
+            let e = ParseError::new(&start, ParseErrorKind::MapRes);
+            *input = start;
+            e
+        })
+    }
+}
+
+/// A tuple of parsers that can be run in sequence.
+pub trait Tuple<'a, O> {
+    /// Runs each parser in turn, returning a tuple of their values.
+    fn parse_tuple(&mut self, input: &mut BitReader<'a>) -> Result<O, ParseError>;
+}
+
+macro_rules! impl_tuple {
+    ($($p:ident $o:ident),*) => {
+        impl<'a, $($o,)* $($p: Parser<'a, $o>),*> Tuple<'a, ($($o,)*)> for ($($p,)*) {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn parse_tuple(&mut self, input: &mut BitReader<'a>) -> Result<($($o,)*), ParseError> {
+                let ($(ref mut $p,)*) = *self;
+                restore(input, |input| Ok(($($p.parse(input)?,)*)))
+            }
+        }
+    };
+}
+
+impl_tuple!(P1 O1);
+impl_tuple!(P1 O1, P2 O2);
+impl_tuple!(P1 O1, P2 O2, P3 O3);
+impl_tuple!(P1 O1, P2 O2, P3 O3, P4 O4);
+impl_tuple!(P1 O1, P2 O2, P3 O3, P4 O4, P5 O5);
+impl_tuple!(P1 O1, P2 O2, P3 O3, P4 O4, P5 O5, P6 O6);
+impl_tuple!(P1 O1, P2 O2, P3 O3, P4 O4, P5 O5, P6 O6, P7 O7);
+impl_tuple!(P1 O1, P2 O2, P3 O3, P4 O4, P5 O5, P6 O6, P7 O7, P8 O8);
+
+/// Returns a parser that runs a tuple of parsers in sequence.
+#[inline]
+pub fn tuple<'a, O, T>(mut parsers: T) -> impl FnMut(&mut BitReader<'a>) -> Result<O, ParseError>
+where
+    T: Tuple<'a, O>,
+{
+    move |input: &mut BitReader<'a>| parsers.parse_tuple(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stream::StreamOrder;
+    use {U1, U4, U12};
+
+    #[test]
+    fn test_errors() {
+        let buf = [0xa5, 0x5a];
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(bits::<U4>()(&mut r), Ok(U4::B1010));
+
+        // A failed tuple reports the position of the failing parser and restores the reader.
+        let e = tuple((bits::<U4>(), tag(U4::B0000)))(&mut r).unwrap_err();
+        assert_eq!(e, ParseError { position: 8, kind: ParseErrorKind::Tag });
+        assert_eq!(r.position(), 4);
+
+        let e = count::<_, _, 2>(bits::<U12>())(&mut r).unwrap_err();
+        assert_eq!(e, ParseError { position: 16, kind: ParseErrorKind::Underrun });
+        assert_eq!(r.position(), 4);
+        assert_eq!(std::format!("{}", e), "unexpected end of input at bit 16");
+
+        assert_eq!(tag(U12::from(0x55au16))(&mut r), Ok(U12::from(0x55au16)));
+        assert_eq!(r.remaining(), 0);
+    }
+
+    #[test]
+    fn test_cond_and_map() {
+        let buf = [0b1000_0001];
+        let mut r = BitReader::new(&buf, StreamOrder::LsbFirst);
+        let flag = map(bits::<U1>(), |b| b == U1::B1)(&mut r).unwrap();
+        assert!(flag);
+        assert_eq!(cond(false, bits::<U4>())(&mut r), Ok(None));
+        assert_eq!(cond(true, bits::<U4>())(&mut r), Ok(Some(U4::B0000)));
+        assert_eq!(r.position(), 5);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_count_vec() {
+        let buf = [0x12, 0x34];
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(count_vec(bits::<U4>(), 3)(&mut r), Ok(std::vec![U4::B0001, U4::B0010, U4::B0011]));
+        assert!(count_vec(bits::<U4>(), 2)(&mut r).is_err());
+        assert_eq!(r.position(), 12);
+    }
+}
@@ -0,0 +1,382 @@
+//! Packed pixel formats from MIPI CSI-2 and video pipelines.
+//!
+//! Each routine loads or stores whole groups as unaligned little-endian u64 words while
+//! eight bytes remain, then finishes the buffer a byte at a time.
+
+use bits::BitWidth;
+use {U10, U12, U14};
+
+/// Checks that `bytes` holds exactly `pixels` pixels packed `group_pixels` to every
+/// `group_bytes` bytes.
+#[inline]
+fn check_len(pixels: usize, bytes: usize, group_pixels: usize, group_bytes: usize) {
+    assert!(pixels % group_pixels == 0, "pixel count is not a multiple of the group size");
+    assert!(bytes == pixels / group_pixels * group_bytes, "buffer length does not match pixel count");
+}
+
+/// Returns the number of steps of `step` bytes through a buffer of `len` bytes for which
+/// an eight-byte access at the start of the step stays in bounds.
+#[inline]
+fn u64_steps(len: usize, step: usize) -> usize {
+    if len < 8 { 0 } else { (len - 8) / step + 1 }
+}
+
+/// Loads the first eight bytes of `src` as a little-endian u64.
+#[inline]
+fn load_u64(src: &[u8]) -> u64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(&src[..8]);
+    u64::from_le_bytes(b)
+}
+
+/// Stores `value` as a little-endian u64 in the first eight bytes of `dst`.
+#[inline]
+fn store_u64(dst: &mut [u8], value: u64) {
+    dst[..8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Unpacks MIPI CSI-2 RAW10 (data type 0x2B) pixels, four to every five bytes.
+///
+/// The first four bytes of each group hold the high eight bits of each pixel, and the fifth
+/// holds their low two bits, first pixel in the least significant bits.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut pixels = [U10::from(0u16); 4];
+/// unpack_raw10(&[0xff, 0x00, 0x55, 0xaa, 0x93], &mut pixels);
+/// assert_eq!(pixels, [U10::from(0x3ffu16), U10::from(0u16), U10::from(0x155u16), U10::from(0x2aau16)]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `dst.len()` is not a multiple of four or `src.len()` is not `dst.len() / 4 * 5`.
+pub fn unpack_raw10(src: &[u8], dst: &mut [U10]) {
+    check_len(dst.len(), src.len(), 4, 5);
+    let n = u64_steps(src.len(), 5);
+    for (i, d) in dst[..n * 4].chunks_exact_mut(4).enumerate() {
+        let w = load_u64(&src[i * 5..]);
+        for (j, p) in d.iter_mut().enumerate() {
+            let (high, low) = (w >> (8 * j), w >> (32 + 2 * j));
+            *p = U10::from_bits_truncate((((high & 0xff) << 2) | (low & 0b11)) as u32);
+        }
+    }
+    unpack_raw10_bytes(&src[n * 5..], &mut dst[n * 4..]);
+}
+
+fn unpack_raw10_bytes(src: &[u8], dst: &mut [U10]) {
+    for (s, d) in src.chunks_exact(5).zip(dst.chunks_exact_mut(4)) {
+        let low = s[4] as u32;
+        for (i, p) in d.iter_mut().enumerate() {
+            *p = U10::from_bits_truncate(((s[i] as u32) << 2) | ((low >> (2 * i)) & 0b11));
+        }
+    }
+}
+
+/// Packs pixels into MIPI CSI-2 RAW10, four to every five bytes.
+///
+/// # Panics
+///
+/// Panics if `src.len()` is not a multiple of four or `dst.len()` is not `src.len() / 4 * 5`.
+pub fn pack_raw10(src: &[U10], dst: &mut [u8]) {
+    check_len(src.len(), dst.len(), 4, 5);
+    let n = u64_steps(dst.len(), 5);
+    // Each store also clears the first three bytes of the next group, which the next
+    // store overwrites.
+    for (i, s) in src[..n * 4].chunks_exact(4).enumerate() {
+        let mut w = 0;
+        for (j, p) in s.iter().enumerate() {
+            let p = p.bits() as u64;
+            w |= ((p >> 2) << (8 * j)) | ((p & 0b11) << (32 + 2 * j));
+        }
+        store_u64(&mut dst[i * 5..], w);
+    }
+    pack_raw10_bytes(&src[n * 4..], &mut dst[n * 5..]);
+}
+
+fn pack_raw10_bytes(src: &[U10], dst: &mut [u8]) {
+    for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(5)) {
+        let mut low = 0;
+        for (i, p) in s.iter().enumerate() {
+            d[i] = (p.bits() >> 2) as u8;
+            low |= (p.bits() & 0b11) << (2 * i);
+        }
+        d[4] = low as u8;
+    }
+}
+
+/// Unpacks MIPI CSI-2 RAW12 (data type 0x2C) pixels, two to every three bytes.
+///
+/// The first two bytes of each group hold the high eight bits of each pixel, and the third
+/// holds their low four bits, first pixel in the least significant bits.
+///
+/// # Panics
+///
+/// Panics if `dst.len()` is not a multiple of two or `src.len()` is not `dst.len() / 2 * 3`.
+pub fn unpack_raw12(src: &[u8], dst: &mut [U12]) {
+    check_len(dst.len(), src.len(), 2, 3);
+    // Each load covers two groups.
+    let n = u64_steps(src.len(), 6);
+    for (i, d) in dst[..n * 4].chunks_exact_mut(4).enumerate() {
+        let w = load_u64(&src[i * 6..]);
+        for (j, p) in d.iter_mut().enumerate() {
+            let base = 24 * (j / 2);
+            let (high, low) = (w >> (base + 8 * (j % 2)), w >> (base + 16 + 4 * (j % 2)));
+            *p = U12::from_bits_truncate((((high & 0xff) << 4) | (low & 0xf)) as u32);
+        }
+    }
+    unpack_raw12_bytes(&src[n * 6..], &mut dst[n * 4..]);
+}
+
+fn unpack_raw12_bytes(src: &[u8], dst: &mut [U12]) {
+    for (s, d) in src.chunks_exact(3).zip(dst.chunks_exact_mut(2)) {
+        let low = s[2] as u32;
+        d[0] = U12::from_bits_truncate(((s[0] as u32) << 4) | (low & 0xf));
+        d[1] = U12::from_bits_truncate(((s[1] as u32) << 4) | (low >> 4));
+    }
+}
+
+/// Packs pixels into MIPI CSI-2 RAW12, two to every three bytes.
+///
+/// # Panics
+///
+/// Panics if `src.len()` is not a multiple of two or `dst.len()` is not `src.len() / 2 * 3`.
+pub fn pack_raw12(src: &[U12], dst: &mut [u8]) {
+    check_len(src.len(), dst.len(), 2, 3);
+    let n = u64_steps(dst.len(), 6);
+    for (i, s) in src[..n * 4].chunks_exact(4).enumerate() {
+        let mut w = 0;
+        for (j, p) in s.iter().enumerate() {
+            let (p, base) = (p.bits() as u64, 24 * (j / 2));
+            w |= ((p >> 4) << (base + 8 * (j % 2))) | ((p & 0xf) << (base + 16 + 4 * (j % 2)));
+        }
+        store_u64(&mut dst[i * 6..], w);
+    }
+    pack_raw12_bytes(&src[n * 4..], &mut dst[n * 6..]);
+}
+
+fn pack_raw12_bytes(src: &[U12], dst: &mut [u8]) {
+    for (s, d) in src.chunks_exact(2).zip(dst.chunks_exact_mut(3)) {
+        let (p0, p1) = (s[0].bits(), s[1].bits());
+        d[0] = (p0 >> 4) as u8;
+        d[1] = (p1 >> 4) as u8;
+        d[2] = ((p0 & 0xf) | ((p1 & 0xf) << 4)) as u8;
+    }
+}
+
+/// Unpacks MIPI CSI-2 RAW14 (data type 0x2D) pixels, four to every seven bytes.
+///
+/// The first four bytes of each group hold the high eight bits of each pixel, and the last
+/// three hold their low six bits as a little-endian 24-bit value, first pixel in the least
+/// significant bits.
+///
+/// # Panics
+///
+/// Panics if `dst.len()` is not a multiple of four or `src.len()` is not `dst.len() / 4 * 7`.
+pub fn unpack_raw14(src: &[u8], dst: &mut [U14]) {
+    check_len(dst.len(), src.len(), 4, 7);
+    let n = u64_steps(src.len(), 7);
+    for (i, d) in dst[..n * 4].chunks_exact_mut(4).enumerate() {
+        let w = load_u64(&src[i * 7..]);
+        for (j, p) in d.iter_mut().enumerate() {
+            let (high, low) = (w >> (8 * j), w >> (32 + 6 * j));
+            *p = U14::from_bits_truncate((((high & 0xff) << 6) | (low & 0x3f)) as u32);
+        }
+    }
+    unpack_raw14_bytes(&src[n * 7..], &mut dst[n * 4..]);
+}
+
+fn unpack_raw14_bytes(src: &[u8], dst: &mut [U14]) {
+    for (s, d) in src.chunks_exact(7).zip(dst.chunks_exact_mut(4)) {
+        let low = s[4] as u32 | ((s[5] as u32) << 8) | ((s[6] as u32) << 16);
+        for (i, p) in d.iter_mut().enumerate() {
+            *p = U14::from_bits_truncate(((s[i] as u32) << 6) | ((low >> (6 * i)) & 0x3f));
+        }
+    }
+}
+
+/// Packs pixels into MIPI CSI-2 RAW14, four to every seven bytes.
+///
+/// # Panics
+///
+/// Panics if `src.len()` is not a multiple of four or `dst.len()` is not `src.len() / 4 * 7`.
+pub fn pack_raw14(src: &[U14], dst: &mut [u8]) {
+    check_len(src.len(), dst.len(), 4, 7);
+    let n = u64_steps(dst.len(), 7);
+    for (i, s) in src[..n * 4].chunks_exact(4).enumerate() {
+        let mut w = 0;
+        for (j, p) in s.iter().enumerate() {
+            let p = p.bits() as u64;
+            w |= ((p >> 6) << (8 * j)) | ((p & 0x3f) << (32 + 6 * j));
+        }
+        store_u64(&mut dst[i * 7..], w);
+    }
+    pack_raw14_bytes(&src[n * 4..], &mut dst[n * 7..]);
+}
+
+fn pack_raw14_bytes(src: &[U14], dst: &mut [u8]) {
+    for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(7)) {
+        let mut low = 0;
+        for (i, p) in s.iter().enumerate() {
+            d[i] = (p.bits() >> 6) as u8;
+            low |= (p.bits() & 0x3f) << (6 * i);
+        }
+        d[4..].copy_from_slice(&low.to_le_bytes()[..3]);
+    }
+}
+
+/// Unpacks 10-bit samples stored P010-style, each in the high bits of a little-endian u16.
+///
+/// This is the sample layout of the P010 video format rather than a CSI-2 data type. The
+/// low six bits of each u16 are ignored.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut samples = [U10::from(0u16); 2];
+/// unpack_p010(&[0xc0, 0xff, 0x40, 0x00], &mut samples);
+/// assert_eq!(samples, [U10::from(0x3ffu16), U10::from(1u16)]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src.len()` is not `2 * dst.len()`.
+pub fn unpack_p010(src: &[u8], dst: &mut [U10]) {
+    check_len(dst.len(), src.len(), 1, 2);
+    let n = src.len() / 8;
+    for (s, d) in src.chunks_exact(8).zip(dst.chunks_exact_mut(4)) {
+        let w = load_u64(s);
+        for (j, p) in d.iter_mut().enumerate() {
+            *p = U10::from_bits_truncate((w >> (16 * j + 6)) as u32);
+        }
+    }
+    unpack_p010_bytes(&src[n * 8..], &mut dst[n * 4..]);
+}
+
+fn unpack_p010_bytes(src: &[u8], dst: &mut [U10]) {
+    for (s, d) in src.chunks_exact(2).zip(dst.iter_mut()) {
+        *d = U10::from_bits_truncate((u16::from_le_bytes([s[0], s[1]]) >> 6) as u32);
+    }
+}
+
+/// Packs 10-bit samples P010-style, each in the high bits of a little-endian u16 with the
+/// low six bits clear.
+///
+/// # Panics
+///
+/// Panics if `dst.len()` is not `2 * src.len()`.
+pub fn pack_p010(src: &[U10], dst: &mut [u8]) {
+    check_len(src.len(), dst.len(), 1, 2);
+    let n = dst.len() / 8;
+    for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(8)) {
+        let mut w = 0;
+        for (j, p) in s.iter().enumerate() {
+            w |= (p.bits() as u64) << (16 * j + 6);
+        }
+        store_u64(d, w);
+    }
+    pack_p010_bytes(&src[n * 4..], &mut dst[n * 8..]);
+}
+
+fn pack_p010_bytes(src: &[U10], dst: &mut [u8]) {
+    for (s, d) in src.iter().zip(dst.chunks_exact_mut(2)) {
+        d.copy_from_slice(&((s.bits() as u16) << 6).to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw10() {
+        let pixels = [0x3ffu16, 0, 0x155, 0x2aa, 0x001, 0x002, 0x100, 0x200].map(U10::from);
+        let mut bytes = [0u8; 10];
+        pack_raw10(&pixels, &mut bytes);
+        assert_eq!(bytes, [0xff, 0x00, 0x55, 0xaa, 0x93, 0x00, 0x00, 0x40, 0x80, 0x09]);
+        let mut out = [U10::from(0u16); 8];
+        unpack_raw10(&bytes, &mut out);
+        assert_eq!(out, pixels);
+    }
+
+    #[test]
+    fn test_raw12() {
+        let pixels = [0xabcu16, 0x123, 0xfff, 0x000].map(U12::from);
+        let mut bytes = [0u8; 6];
+        pack_raw12(&pixels, &mut bytes);
+        assert_eq!(bytes, [0xab, 0x12, 0x3c, 0xff, 0x00, 0x0f]);
+        let mut out = [U12::from(0u16); 4];
+        unpack_raw12(&bytes, &mut out);
+        assert_eq!(out, pixels);
+    }
+
+    #[test]
+    fn test_raw14() {
+        let pixels = [0x3fffu16, 0x0001, 0x2000, 0x1234].map(U14::from);
+        let mut bytes = [0u8; 7];
+        pack_raw14(&pixels, &mut bytes);
+        assert_eq!(bytes, [0xff, 0x00, 0x80, 0x48, 0x7f, 0x00, 0xd0]);
+        let mut out = [U14::from(0u16); 4];
+        unpack_raw14(&bytes, &mut out);
+        assert_eq!(out, pixels);
+    }
+
+    #[test]
+    fn test_p010() {
+        let samples = [0x3ffu16, 0x001, 0x200].map(U10::from);
+        let mut bytes = [0u8; 6];
+        pack_p010(&samples, &mut bytes);
+        assert_eq!(bytes, [0xc0, 0xff, 0x40, 0x00, 0x00, 0x80]);
+        let mut out = [U10::from(0u16); 3];
+        unpack_p010(&bytes, &mut out);
+        assert_eq!(out, samples);
+    }
+
+    #[test]
+    fn test_u64_paths() {
+        // Compare the u64 paths against the byte-wise paths for every buffer length
+        // up to twelve groups, so that each remainder is exercised.
+        let bytes: [u8; 84] = core::array::from_fn(|i| (i as u32).wrapping_mul(0x9e37_79b9).to_le_bytes()[3]);
+        let (mut a, mut b) = ([0u8; 84], [0u8; 84]);
+        for groups in 0..=12 {
+            let (mut p, mut q) = ([U10::from(0u16); 48], [U10::from(0u16); 48]);
+            unpack_raw10(&bytes[..groups * 5], &mut p[..groups * 4]);
+            unpack_raw10_bytes(&bytes[..groups * 5], &mut q[..groups * 4]);
+            assert_eq!(p, q);
+            pack_raw10(&p[..groups * 4], &mut a[..groups * 5]);
+            assert_eq!(a[..groups * 5], bytes[..groups * 5]);
+            unpack_p010(&bytes[..groups * 6], &mut p[..groups * 3]);
+            unpack_p010_bytes(&bytes[..groups * 6], &mut q[..groups * 3]);
+            assert_eq!(p, q);
+            pack_p010(&p[..groups * 3], &mut a[..groups * 6]);
+            pack_p010_bytes(&q[..groups * 3], &mut b[..groups * 6]);
+            assert_eq!(a[..groups * 6], b[..groups * 6]);
+
+            let (mut p, mut q) = ([U12::from(0u16); 24], [U12::from(0u16); 24]);
+            unpack_raw12(&bytes[..groups * 3], &mut p[..groups * 2]);
+            unpack_raw12_bytes(&bytes[..groups * 3], &mut q[..groups * 2]);
+            assert_eq!(p, q);
+            pack_raw12(&p[..groups * 2], &mut a[..groups * 3]);
+            assert_eq!(a[..groups * 3], bytes[..groups * 3]);
+
+            let (mut p, mut q) = ([U14::from(0u16); 48], [U14::from(0u16); 48]);
+            unpack_raw14(&bytes[..groups * 7], &mut p[..groups * 4]);
+            unpack_raw14_bytes(&bytes[..groups * 7], &mut q[..groups * 4]);
+            assert_eq!(p, q);
+            pack_raw14(&p[..groups * 4], &mut a[..groups * 7]);
+            assert_eq!(a[..groups * 7], bytes[..groups * 7]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel count is not a multiple of the group size")]
+    fn test_partial_group() {
+        pack_raw10(&[U10::from(0u16); 3], &mut [0; 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer length does not match pixel count")]
+    fn test_short_buffer() {
+        unpack_raw12(&[0; 5], &mut [U12::from(0u16); 4]);
+    }
+}
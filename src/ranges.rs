@@ -119,30 +119,72 @@ macro_rules! impl_range {
         impl $id {
             #[inline]
             /// Returns the primitive representation of the value.            
-            pub fn value(&self) -> usize {
+            pub const fn value(&self) -> usize {
                 *self as usize
             }
 
+            #[inline]
+            /// Reinterprets a slice of primitives as a slice of values, after checking that
+            /// every element is within the range of the type.
+            pub fn try_cast_slice(slice: &[usize]) -> Result<&[Self], RangeError> {
+                match slice.iter().position(|&v| v >= $max) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a mutable slice of primitives as a slice of values, after checking
+            /// that every element is within the range of the type.
+            pub fn try_cast_slice_mut(slice: &mut [usize]) -> Result<&mut [Self], RangeError> {
+                match slice.iter().position(|&v| v >= $max) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a slice of values as a slice of their primitive representations.
+            pub fn as_primitive_slice(slice: &[Self]) -> &[usize] {
+                unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const usize, slice.len()) }
+            }
+
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u8_unchecked(other: u8) -> Self {
                 transmute(other as usize)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u16_unchecked(other: u16) -> Self {
                 transmute(other as usize)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u32_unchecked(other: u32) -> Self {
                 transmute(other as usize)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_usize_unchecked(other: usize) -> Self {
                 transmute(other as usize)
             }
@@ -0,0 +1,406 @@
+use core::marker::PhantomData;
+use core::ptr;
+
+use access::{Access, Readable, Writable};
+use bits::Word;
+use field::BitRange;
+use field_set::FieldSet;
+
+/// Describes a register: its word type and the fields within it.
+///
+/// u8, u16, u32 and u64 are register specs with no declared fields, so `Register<u32>`
+/// is a plain 32-bit register.
+///
+/// Fields are listed as a tuple. Their access policies determine which bits `modify()`
+/// must not write back.
+pub trait RegisterSpec {
+    /// The word type of the register.
+    type Word: Word;
+    /// The fields of the register, as a tuple of `BitRange` types.
+    type Fields: FieldList;
+    /// The value of the register after reset.
+    const RESET: Self::Word = <Self::Word as Word>::ZERO;
+}
+
+macro_rules! impl_register_spec {
+    ($($t:ty),*) => (
+        $(
+        impl RegisterSpec for $t {
+            type Word = $t;
+            type Fields = ();
+        }
+        )*
+    )
+}
+
+impl_register_spec!(u8, u16, u32, u64);
+
+/// A list of fields.
+///
+/// Implemented for every `BitRange` type and for tuples of up to 16 field lists, so
+/// registers with more than 16 fields can nest tuples.
+pub trait FieldList {
+    /// The mask of all bits in fields that must not be written back after a read.
+    const NO_WRITE_BACK: u64;
+}
+
+impl FieldList for () {
+    const NO_WRITE_BACK: u64 = 0;
+}
+
+impl<F: BitRange> FieldList for F {
+    const NO_WRITE_BACK: u64 = if <F::Access as Access>::KIND.is_write_back() { 0 } else { F::MASK };
+}
+
+macro_rules! impl_field_list {
+    ($($f:ident),*) => (
+        impl<$($f: FieldList),*> FieldList for ($($f,)*) {
+            const NO_WRITE_BACK: u64 = 0 $(| $f::NO_WRITE_BACK)*;
+        }
+    )
+}
+
+impl_field_list!(A);
+impl_field_list!(A, B);
+impl_field_list!(A, B, C);
+impl_field_list!(A, B, C, D);
+impl_field_list!(A, B, C, D, E);
+impl_field_list!(A, B, C, D, E, F);
+impl_field_list!(A, B, C, D, E, F, G);
+impl_field_list!(A, B, C, D, E, F, G, H);
+impl_field_list!(A, B, C, D, E, F, G, H, I);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K, L);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+impl_field_list!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+
+/// A bus through which registers are read and written.
+///
+/// All accesses are of a full u8, u16, u32 or u64 word.
+///
+/// # Safety
+///
+/// Implementations must be sound for every address that satisfies the validity contract
+/// documented by the bus. A bus that checks addresses itself, such as `SimBus`, may accept
+/// any address.
+pub unsafe trait Bus {
+    /// Reads the word at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be valid for reads of a `W` on this bus.
+    unsafe fn read<W: Word>(&self, addr: usize) -> W;
+
+    /// Writes `value` to the word at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be valid for writes of a `W` on this bus.
+    unsafe fn write<W: Word>(&self, addr: usize, value: W);
+}
+
+/// Memory-mapped I/O using volatile reads and writes.
+///
+/// An address is valid on this bus if it is valid for volatile reads or writes of the
+/// word. `Mmio` can only be obtained through the unsafe register constructors.
+#[derive(Clone, Copy, Debug)]
+pub struct Mmio(());
+
+unsafe impl Bus for Mmio {
+    #[inline]
+    unsafe fn read<W: Word>(&self, addr: usize) -> W {
+        ptr::read_volatile(addr as *const W)
+    }
+
+    #[inline]
+    unsafe fn write<W: Word>(&self, addr: usize, value: W) {
+        ptr::write_volatile(addr as *mut W, value)
+    }
+}
+
+impl Mmio {
+    /// The memory-mapped bus, for use by unsafe constructors within the crate.
+    pub(crate) const BUS: Mmio = Mmio(());
+}
+
+/// A register described by the spec `S`, accessed through the bus `B`.
+///
+/// By default registers are memory-mapped and all accesses are single volatile reads and
+/// writes of the full word. Fields can only be read through `read_field` if they are
+/// `Readable`, and only written through `modify_field` if they are `Writable`; the access
+/// policies `RO`, `WO`, `RW`, `W1C`, `W1S` and `RC` are checked at compile time.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// type En = Field<0, U1>;
+/// type Mode = Field<4, U4>;
+///
+/// let mut mem = 0u32;
+/// let ctrl = unsafe { Register::<u32>::new(&mut mem) };
+///
+/// ctrl.write(0x0000_0030);
+/// assert_eq!(ctrl.read_field::<Mode>(), U4::B0011);
+///
+/// ctrl.modify(|r| r.with_field_at::<0>(U1::B1));
+/// ctrl.modify_field::<Mode>(U4::B1000);
+/// assert_eq!(ctrl.read(), 0x0000_0081);
+/// assert_eq!(ctrl.read_field::<En>(), U1::B1);
+/// ```
+///
+/// Declaring the fields of a register lets `modify()` avoid writing back set bits in
+/// write-one-to-clear and write-one-to-set fields:
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// type Ready = Field<0, U1, RO>;
+/// type Overrun = Field<1, U1, W1C>;
+/// type Enable = Field<8, U1>;
+///
+/// struct Status;
+///
+/// impl RegisterSpec for Status {
+///     type Word = u32;
+///     type Fields = (Ready, Overrun, Enable);
+/// }
+///
+/// let mut mem = 0b11u32;
+/// let status = unsafe { Register::<Status>::new(&mut mem) };
+///
+/// // The overrun flag is written as 0, which leaves it pending on real hardware.
+/// // Plain memory just stores the written value.
+/// status.modify_field::<Enable>(U1::B1);
+/// assert_eq!(status.read(), 0x101);
+/// assert_eq!(status.read_field::<Ready>(), U1::B1);
+/// ```
+///
+/// Read-only fields cannot be written:
+///
+/// ```compile_fail
+/// use bobbin_bits::*;
+///
+/// let mut mem = 0u32;
+/// let r = unsafe { Register::<u32>::new(&mut mem) };
+/// r.modify_field::<Field<0, U1, RO>>(U1::B1);
+/// ```
+///
+/// and write-only fields cannot be read:
+///
+/// ```compile_fail
+/// use bobbin_bits::*;
+///
+/// let mut mem = 0u32;
+/// let r = unsafe { Register::<u32>::new(&mut mem) };
+/// r.read_field::<Field<0, U1, WO>>();
+/// ```
+pub struct Register<S: RegisterSpec, B: Bus = Mmio> {
+    bus: B,
+    addr: usize,
+    _spec: PhantomData<S>,
+}
+
+impl<S: RegisterSpec> Register<S> {
+    /// Constructs a memory-mapped register at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for volatile reads and writes of the register word for as
+    /// long as the register is used.
+    #[inline]
+    pub unsafe fn new(ptr: *mut S::Word) -> Self {
+        Register::at(ptr as usize)
+    }
+
+    /// Constructs a memory-mapped register at the address `addr`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be valid for volatile reads and writes of the register word for as
+    /// long as the register is used.
+    #[inline]
+    pub const unsafe fn at(addr: usize) -> Self {
+        Register { bus: Mmio::BUS, addr, _spec: PhantomData }
+    }
+
+    /// Returns the pointer to the register.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut S::Word {
+        self.addr as *mut S::Word
+    }
+}
+
+impl<S: RegisterSpec, B: Bus> Register<S, B> {
+    /// Constructs a register at the address `addr` on `bus`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be valid for reads and writes of the register word on `bus` for as
+    /// long as the register is used.
+    #[inline]
+    pub const unsafe fn with_bus(bus: B, addr: usize) -> Self {
+        Register { bus, addr, _spec: PhantomData }
+    }
+
+    /// Returns the address of the register.
+    #[inline]
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Reads the register.
+    #[inline]
+    pub fn read(&self) -> S::Word {
+        // The constructors require `addr` to be valid on the bus.
+        unsafe { self.bus.read(self.addr) }
+    }
+
+    /// Writes `value` to the register.
+    #[inline]
+    pub fn write(&self, value: S::Word) {
+        unsafe { self.bus.write(self.addr, value) }
+    }
+
+    /// Reads the register, passes the value to `f` and writes back the result.
+    ///
+    /// Bits in fields that must not be written back, such as write-one-to-clear
+    /// fields, are cleared before the value is passed to `f`.
+    #[inline]
+    pub fn modify<F: FnOnce(S::Word) -> S::Word>(&self, f: F) {
+        let mask = S::Word::from_u64_truncate(!<S::Fields as FieldList>::NO_WRITE_BACK);
+        let value = S::Word::from_u64_truncate(self.read().to_u64() & mask.to_u64());
+        self.write(f(value))
+    }
+
+    /// Reads the register and extracts the field `F`.
+    #[inline]
+    pub fn read_field<F>(&self) -> F::Value
+    where
+        F: BitRange,
+        F::Access: Readable,
+    {
+        F::read(self.read())
+    }
+
+    /// Reads the register, replaces the field `F` with `value` and writes back the result.
+    #[inline]
+    pub fn modify_field<F>(&self, value: F::Value)
+    where
+        F: BitRange,
+        F::Access: Writable,
+    {
+        self.modify(|r| F::write(r, value))
+    }
+
+    /// Applies all of the assignments in `set` with a single read-modify-write.
+    #[inline]
+    pub fn modify_fields(&self, set: FieldSet<S::Word>) {
+        self.modify(|r| set.apply(r))
+    }
+
+    /// Writes the reset value of the register with all of the assignments in `set`
+    /// applied, without reading the register.
+    #[inline]
+    pub fn write_fields(&self, set: FieldSet<S::Word>) {
+        self.write(set.apply(S::RESET))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use access::{RO, W1C, W1S};
+    use field::Field;
+    use {U1, U4, U8, U12};
+
+    #[test]
+    fn test_register_buffer() {
+        let mut mem = [0u32; 4];
+        let r1 = unsafe { Register::<u32>::new(&mut mem[1]) };
+        let r2 = unsafe { Register::<u32>::new(&mut mem[2]) };
+
+        r1.write(0x1234_5678);
+        r2.write(0xffff_ffff);
+        r2.modify(|r| r & 0xf);
+        assert_eq!(r1.read(), 0x1234_5678);
+        assert_eq!(r2.read(), 0xf);
+        assert_eq!(mem, [0, 0x1234_5678, 0xf, 0]);
+    }
+
+    #[test]
+    fn test_register_fields() {
+        type Lo = Field<0, U4>;
+        type Mid = Field<4, U12>;
+        type Top = Field<15, U1>;
+
+        let mut mem = 0u16;
+        let r = unsafe { Register::<u16>::new(&mut mem) };
+        r.modify_field::<Mid>(U12::from(0xabcu16));
+        r.modify_field::<Lo>(U4::B0101);
+        assert_eq!(r.read(), 0xabc5);
+        assert_eq!(r.read_field::<Top>(), U1::B1);
+        assert_eq!(r.read_field::<Mid>(), U12::from(0xabcu16));
+
+        let mut mem = 0u8;
+        let r = unsafe { Register::<u8>::new(&mut mem) };
+        r.modify_field::<Field<0, U8>>(U8::from(0x5au8));
+        assert_eq!(r.read(), 0x5a);
+    }
+
+    #[test]
+    fn test_register_access() {
+        type Clear = Field<0, U1, W1C>;
+        type Set = Field<1, U1, W1S>;
+        type Mode = Field<4, U4>;
+        type Count = Field<8, U8, RO>;
+
+        struct Spec;
+
+        impl RegisterSpec for Spec {
+            type Word = u32;
+            type Fields = (Clear, Set, Mode, Count);
+        }
+
+        assert_eq!(<Spec as RegisterSpec>::Fields::NO_WRITE_BACK, 0b11);
+        assert_eq!(<((Mode, Clear), (Count, (Set,))) as FieldList>::NO_WRITE_BACK, 0b11);
+
+        // Plain memory does not implement the access policies, so the bits that
+        // modify() did not write back read as zero afterwards.
+        let mut mem = 0x0000_ab03u32;
+        let r = unsafe { Register::<Spec>::new(&mut mem) };
+        r.modify_field::<Mode>(U4::B0101);
+        assert_eq!(r.read(), 0x0000_ab50);
+        r.modify_field::<Clear>(U1::B1);
+        assert_eq!(r.read(), 0x0000_ab51);
+        r.modify(|v| v | 0b10);
+        assert_eq!(r.read(), 0x0000_ab52);
+        assert_eq!(r.read_field::<Count>(), U8::from(0xabu8));
+        assert_eq!(r.read_field::<Set>(), U1::B1);
+    }
+
+    #[test]
+    fn test_register_field_set() {
+        type En = Field<0, U1>;
+        type Mode = Field<4, U4>;
+        type Div = Field<8, U8>;
+
+        struct Ctrl;
+
+        impl RegisterSpec for Ctrl {
+            type Word = u32;
+            type Fields = (En, Mode, Div);
+            const RESET: u32 = 0x8000_0000;
+        }
+
+        let mut mem = 0x0000_0f00u32;
+        let r = unsafe { Register::<Ctrl>::new(&mut mem) };
+        let set = FieldSet::new().with::<En>(U1::B1).with::<Mode>(U4::B0110);
+        r.modify_fields(set);
+        assert_eq!(r.read(), 0x0000_0f61);
+        r.write_fields(set);
+        assert_eq!(r.read(), 0x8000_0061);
+    }
+}
@@ -0,0 +1,434 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+use access::{Access, AccessKind};
+use bits::{BitWidth, Word};
+use field::BitRange;
+use register::{Bus, Register, RegisterSpec};
+
+/// A named field of a simulated register.
+struct SimField {
+    name: &'static str,
+    mask: u64,
+    width: u32,
+    kind: AccessKind,
+    decode: fn(u64) -> u32,
+}
+
+fn decode<F: BitRange>(value: u64) -> u32 {
+    F::read(value).bits()
+}
+
+/// A simulated register with a reset value, field access policies and optional
+/// side effects.
+///
+/// Bits that are not covered by any field are read-write.
+pub struct SimRegister {
+    name: &'static str,
+    addr: usize,
+    bits: u32,
+    reset: u64,
+    value: u64,
+    fields: Vec<SimField>,
+    on_read: Option<Box<dyn FnMut(u64) -> u64>>,
+    on_write: Option<Box<dyn FnMut(u64) -> u64>>,
+}
+
+impl SimRegister {
+    /// Constructs a register described by `S` at `addr`, initialized to `S::RESET`.
+    pub fn new<S: RegisterSpec>(name: &'static str, addr: usize) -> Self {
+        let reset = S::RESET.to_u64();
+        SimRegister {
+            name,
+            addr,
+            bits: <S::Word as Word>::BITS,
+            reset,
+            value: reset,
+            fields: Vec::new(),
+            on_read: None,
+            on_write: None,
+        }
+    }
+
+    /// Sets the reset value of the register.
+    pub fn with_reset(mut self, value: u64) -> Self {
+        self.reset = value & self.word_mask();
+        self.value = self.reset;
+        self
+    }
+
+    /// Adds the field `F`, which is simulated with the access policy of `F`.
+    pub fn field<F: BitRange>(mut self, name: &'static str) -> Self {
+        self.fields.push(SimField {
+            name,
+            mask: F::MASK,
+            width: <F::Value as BitWidth>::WIDTH,
+            kind: <F::Access as Access>::KIND,
+            decode: decode::<F>,
+        });
+        self
+    }
+
+    /// Sets a function that is called after every read with the stored value, returning
+    /// the new stored value.
+    pub fn on_read<F: FnMut(u64) -> u64 + 'static>(mut self, f: F) -> Self {
+        self.on_read = Some(Box::new(f));
+        self
+    }
+
+    /// Sets a function that is called after every write with the stored value, returning
+    /// the new stored value.
+    pub fn on_write<F: FnMut(u64) -> u64 + 'static>(mut self, f: F) -> Self {
+        self.on_write = Some(Box::new(f));
+        self
+    }
+
+    fn word_mask(&self) -> u64 {
+        !0 >> (64 - self.bits)
+    }
+
+    fn read(&mut self) -> u64 {
+        let mut result = self.value;
+        let mut after = self.value;
+        for f in self.fields.iter() {
+            match f.kind {
+                AccessKind::WriteOnly => result &= !f.mask,
+                AccessKind::ReadToClear => after &= !f.mask,
+                _ => {}
+            }
+        }
+        self.value = after;
+        if let Some(ref mut on_read) = self.on_read {
+            self.value = on_read(self.value) & self.word_mask();
+        }
+        result
+    }
+
+    fn write(&mut self, value: u64) {
+        let old = self.value;
+        let mut new = value;
+        for f in self.fields.iter() {
+            let bits = match f.kind {
+                AccessKind::ReadOnly | AccessKind::ReadToClear => old,
+                AccessKind::WriteOnly | AccessKind::ReadWrite => value,
+                AccessKind::WriteOneToClear => old & !value,
+                AccessKind::WriteOneToSet => old | value,
+            };
+            new = (new & !f.mask) | (bits & f.mask);
+        }
+        self.value = new & self.word_mask();
+        if let Some(ref mut on_write) = self.on_write {
+            self.value = on_write(self.value) & self.word_mask();
+        }
+    }
+}
+
+/// The direction of a bus access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceOp {
+    /// A read from a register.
+    Read,
+    /// A write to a register.
+    Write,
+}
+
+/// A single access recorded by a `SimBus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The direction of the access.
+    pub op: TraceOp,
+    /// The address of the register.
+    pub addr: usize,
+    /// The value read or written.
+    pub value: u64,
+}
+
+impl TraceEntry {
+    /// Constructs a read of `value` from `addr`.
+    pub const fn read(addr: usize, value: u64) -> Self {
+        TraceEntry { op: TraceOp::Read, addr, value }
+    }
+
+    /// Constructs a write of `value` to `addr`.
+    pub const fn write(addr: usize, value: u64) -> Self {
+        TraceEntry { op: TraceOp::Write, addr, value }
+    }
+
+    /// Extracts the field `F` from the value read or written.
+    pub fn field<F: BitRange>(&self) -> F::Value {
+        F::read(self.value)
+    }
+}
+
+/// A simulated peripheral bus for testing register drivers without hardware.
+///
+/// Registers are held in memory and apply their field access policies, and every access
+/// through the bus is recorded in a trace.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// type Ready = Field<0, U1, RC>;
+/// type Overrun = Field<1, U1, W1C>;
+/// type Enable = Field<8, U1>;
+///
+/// struct Status;
+///
+/// impl RegisterSpec for Status {
+///     type Word = u32;
+///     type Fields = (Ready, Overrun, Enable);
+/// }
+///
+/// let mut bus = SimBus::new();
+/// bus.add(SimRegister::new::<Status>("STATUS", 0x4000_0000)
+///     .with_reset(0b11)
+///     .field::<Ready>("READY")
+///     .field::<Overrun>("OVR")
+///     .field::<Enable>("EN"));
+///
+/// let status = bus.register::<Status>(0x4000_0000);
+/// status.modify_field::<Enable>(U1::B1);
+/// status.modify_field::<Overrun>(U1::B1);
+///
+/// let trace = bus.take_trace();
+/// assert_eq!(trace, [
+///     TraceEntry::read(0x4000_0000, 0x003),
+///     TraceEntry::write(0x4000_0000, 0x101),
+///     TraceEntry::read(0x4000_0000, 0x102),
+///     TraceEntry::write(0x4000_0000, 0x102),
+/// ]);
+/// assert_eq!(trace[1].field::<Enable>(), U1::B1);
+/// assert_eq!(format!("{}", bus.describe(&trace[1])),
+///     "W 0x40000000 STATUS 0x00000101 READY=0b1 OVR=0b0 EN=0b1");
+/// assert_eq!(bus.peek(0x4000_0000), 0x100);
+/// ```
+#[derive(Default)]
+pub struct SimBus {
+    registers: RefCell<Vec<SimRegister>>,
+    trace: RefCell<Vec<TraceEntry>>,
+}
+
+impl SimBus {
+    /// Constructs a bus with no registers.
+    pub fn new() -> Self {
+        SimBus::default()
+    }
+
+    /// Adds a register to the bus.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already a register at the same address.
+    pub fn add(&mut self, register: SimRegister) {
+        let registers = self.registers.get_mut();
+        assert!(registers.iter().all(|r| r.addr != register.addr), "duplicate register address");
+        registers.push(register);
+    }
+
+    /// Returns a register described by `S` at `addr` on this bus.
+    ///
+    /// This is safe because the bus checks every access against its registers.
+    pub fn register<S: RegisterSpec>(&self, addr: usize) -> Register<S, &SimBus> {
+        unsafe { Register::with_bus(self, addr) }
+    }
+
+    /// Returns the stored value of the register at `addr` without side effects or tracing.
+    pub fn peek(&self, addr: usize) -> u64 {
+        self.with_register(addr, |r| r.value)
+    }
+
+    /// Sets the stored value of the register at `addr` without side effects or tracing.
+    pub fn poke(&self, addr: usize, value: u64) {
+        self.with_register(addr, |r| r.value = value & r.word_mask())
+    }
+
+    /// Returns all registers to their reset values and clears the trace.
+    pub fn reset(&self) {
+        for r in self.registers.borrow_mut().iter_mut() {
+            r.value = r.reset;
+        }
+        self.trace.borrow_mut().clear();
+    }
+
+    /// Returns a copy of the trace.
+    pub fn trace(&self) -> Vec<TraceEntry> {
+        self.trace.borrow().clone()
+    }
+
+    /// Returns the trace, leaving it empty.
+    pub fn take_trace(&self) -> Vec<TraceEntry> {
+        self.trace.replace(Vec::new())
+    }
+
+    /// Returns a value that formats `entry` with the register and field names.
+    pub fn describe<'a>(&'a self, entry: &'a TraceEntry) -> Describe<'a> {
+        Describe { bus: self, entry }
+    }
+
+    fn with_register<T, F: FnOnce(&mut SimRegister) -> T>(&self, addr: usize, f: F) -> T {
+        let mut registers = self.registers.borrow_mut();
+        match registers.iter_mut().find(|r| r.addr == addr) {
+            Some(r) => f(r),
+            None => panic!("no register at address 0x{:08x}", addr),
+        }
+    }
+
+    fn check_width(r: &SimRegister, bits: u32) {
+        assert!(r.bits == bits, "{}-bit access to {}-bit register {}", bits, r.bits, r.name);
+    }
+}
+
+// Every address is valid: accesses to addresses without a register panic.
+unsafe impl Bus for &SimBus {
+    unsafe fn read<W: Word>(&self, addr: usize) -> W {
+        let value = self.with_register(addr, |r| {
+            SimBus::check_width(r, W::BITS);
+            r.read()
+        });
+        self.trace.borrow_mut().push(TraceEntry::read(addr, value));
+        W::from_u64_truncate(value)
+    }
+
+    unsafe fn write<W: Word>(&self, addr: usize, value: W) {
+        let value = value.to_u64();
+        self.with_register(addr, |r| {
+            SimBus::check_width(r, W::BITS);
+            r.write(value)
+        });
+        self.trace.borrow_mut().push(TraceEntry::write(addr, value));
+    }
+}
+
+/// Formats a `TraceEntry` with register and field names. Returned by `SimBus::describe`.
+pub struct Describe<'a> {
+    bus: &'a SimBus,
+    entry: &'a TraceEntry,
+}
+
+impl<'a> fmt::Display for Describe<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.entry.op {
+            TraceOp::Read => "R",
+            TraceOp::Write => "W",
+        };
+        let registers = self.bus.registers.borrow();
+        let r = match registers.iter().find(|r| r.addr == self.entry.addr) {
+            Some(r) => r,
+            None => return write!(f, "{} 0x{:08x} 0x{:x}", op, self.entry.addr, self.entry.value),
+        };
+        write!(f, "{} 0x{:08x} {} 0x{:0digits$x}", op, r.addr, r.name, self.entry.value,
+            digits = (r.bits / 4) as usize)?;
+        for field in r.fields.iter() {
+            let value = (field.decode)(self.entry.value);
+            if field.width <= 6 {
+                write!(f, " {}=0b{:0width$b}", field.name, value, width = field.width as usize)?;
+            } else {
+                write!(f, " {}=0x{:0digits$x}", field.name, value,
+                    digits = field.width.div_ceil(4) as usize)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use access::{RO, W1S, WO};
+    use field::Field;
+    use {U1, U4, U8, U12};
+
+    type Data = Field<0, U8>;
+    type Busy = Field<8, U1, RO>;
+    type Start = Field<9, U1, W1S>;
+    type Key = Field<12, U4, WO>;
+
+    struct Ctrl;
+
+    impl RegisterSpec for Ctrl {
+        type Word = u16;
+        type Fields = (Data, Busy, Start, Key);
+        const RESET: u16 = 0x0100;
+    }
+
+    fn bus() -> SimBus {
+        let mut bus = SimBus::new();
+        bus.add(SimRegister::new::<Ctrl>("CTRL", 0x10)
+            .field::<Data>("DATA")
+            .field::<Busy>("BUSY")
+            .field::<Start>("START")
+            .field::<Key>("KEY"));
+        bus.add(SimRegister::new::<u32>("COUNT", 0x20).on_read(|v| v + 1));
+        bus
+    }
+
+    #[test]
+    fn test_sim_policies() {
+        let bus = bus();
+        let ctrl = bus.register::<Ctrl>(0x10);
+        assert_eq!(ctrl.read(), 0x0100);
+
+        // BUSY is read-only, KEY is write-only
+        ctrl.write(0xf2ff);
+        assert_eq!(bus.peek(0x10), 0xf3ff);
+        assert_eq!(ctrl.read(), 0x03ff);
+
+        // START cannot be cleared by writing 0
+        ctrl.write(0x0000);
+        assert_eq!(bus.peek(0x10), 0x0300);
+
+        // modify() does not write back the set START bit
+        bus.poke(0x10, 0x0100);
+        ctrl.modify_field::<Data>(U8::from(0x55u8));
+        assert_eq!(bus.peek(0x10), 0x0155);
+    }
+
+    #[test]
+    fn test_sim_trace() {
+        let bus = bus();
+        let ctrl = bus.register::<Ctrl>(0x10);
+        let count = bus.register::<u32>(0x20);
+
+        ctrl.modify_fields(::field_set::FieldSet::new()
+            .with::<Start>(U1::B1)
+            .with::<Key>(U4::B1010));
+        assert_eq!(count.read(), 0);
+        assert_eq!(count.read(), 1);
+        assert_eq!(bus.peek(0x20), 2);
+
+        let trace = bus.take_trace();
+        assert_eq!(trace, [
+            TraceEntry::read(0x10, 0x0100),
+            TraceEntry::write(0x10, 0xa300),
+            TraceEntry::read(0x20, 0),
+            TraceEntry::read(0x20, 1),
+        ]);
+        assert_eq!(trace[1].field::<Key>(), U4::B1010);
+        assert_eq!(format!("{}", bus.describe(&trace[1])),
+            "W 0x00000010 CTRL 0xa300 DATA=0x00 BUSY=0b1 START=0b1 KEY=0b1010");
+        assert_eq!(format!("{}", bus.describe(&trace[2])), "R 0x00000020 COUNT 0x00000000");
+        assert!(bus.trace().is_empty());
+
+        bus.reset();
+        assert_eq!(bus.peek(0x10), 0x0100);
+        assert_eq!(bus.peek(0x20), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "32-bit access to 16-bit register CTRL")]
+    fn test_sim_width() {
+        let bus = bus();
+        bus.register::<u32>(0x10).read();
+    }
+
+    #[test]
+    fn test_sim_field_width() {
+        let mut bus = SimBus::new();
+        bus.add(SimRegister::new::<u32>("WIDE", 0).field::<Field<4, U12>>("F"));
+        bus.register::<u32>(0).write(0x0000_abc0);
+        let trace = bus.take_trace();
+        assert_eq!(format!("{}", bus.describe(&trace[0])), "W 0x00000000 WIDE 0x0000abc0 F=0xabc");
+    }
+}
@@ -0,0 +1,464 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+use bits::BitWidth;
+use bytes::{BitOrder, ByteFieldExt};
+use U1;
+
+/// The order in which the bits of a stream fill each byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamOrder {
+    /// Bits fill each byte from the most significant, and values are stored most
+    /// significant bit first, as in most network protocols and video codecs.
+    MsbFirst,
+    /// Bits fill each byte from the least significant, and values are stored least
+    /// significant bit first, as in DEFLATE.
+    LsbFirst,
+}
+
+impl StreamOrder {
+    /// Returns the order under which stream positions are field offsets.
+    #[inline]
+    fn bit_order(self) -> BitOrder {
+        match self {
+            StreamOrder::MsbFirst => BitOrder::BE_MSB0,
+            StreamOrder::LsbFirst => BitOrder::LE_LSB0,
+        }
+    }
+}
+
+/// An error reading or writing a bit stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamError {
+    /// Fewer bits remain in the buffer than were requested.
+    Underrun,
+    /// The buffer has no room for the bits being written.
+    Overflow,
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StreamError::Underrun => f.write_str("not enough bits left to read"),
+            StreamError::Overflow => f.write_str("not enough room left to write"),
+        }
+    }
+}
+
+/// Reads a sequence of bit fields from a byte buffer.
+///
+/// Reads past the end of the buffer return `StreamError::Underrun` rather than panicking.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut r = BitReader::new(&[0b1011_0110, 0b0101_1010, 0xff], StreamOrder::MsbFirst);
+/// assert_eq!(r.read::<U3>(), Ok(U3::B101));
+/// assert_eq!(r.read::<U5>(), Ok(U5::B10110));
+/// assert_eq!(r.read::<U12>(), Ok(U12::from(0x5afu16)));
+/// assert_eq!(r.read_bool(), Ok(true));
+/// r.align_to_byte();
+/// assert_eq!(r.read::<U1>(), Err(StreamError::Underrun));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    order: StreamOrder,
+}
+
+impl<'a> BitReader<'a> {
+    /// Constructs a reader positioned at the start of `buf`.
+    #[inline]
+    pub fn new(buf: &'a [u8], order: StreamOrder) -> Self {
+        BitReader { buf, pos: 0, order }
+    }
+
+    /// Returns the order in which bits are read.
+    #[inline]
+    pub fn order(&self) -> StreamOrder {
+        self.order
+    }
+
+    /// Returns the number of bits read or skipped so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the number of bits left to read.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.buf.len() * 8 - self.pos
+    }
+
+    /// Returns `true` if the reader is at a byte boundary.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.pos % 8 == 0
+    }
+
+    /// Returns the next `width` bits without consuming them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32.
+    #[inline]
+    pub fn peek_bits(&self, width: u32) -> Result<u32, StreamError> {
+        if self.remaining() < width as usize {
+            return Err(StreamError::Underrun);
+        }
+        Ok(self.buf.get_bits(self.pos, width, self.order.bit_order()))
+    }
+
+    /// Reads `width` bits into the low bits of a u32.
+    ///
+    /// On error the reader is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32.
+    #[inline]
+    pub fn read_bits(&mut self, width: u32) -> Result<u32, StreamError> {
+        let value = self.peek_bits(width)?;
+        self.pos += width as usize;
+        Ok(value)
+    }
+
+    /// Returns the next field of type `T` without consuming it.
+    #[inline]
+    pub fn peek<T: BitWidth>(&self) -> Result<T, StreamError> {
+        self.peek_bits(T::WIDTH).map(T::from_bits_truncate)
+    }
+
+    /// Reads a field of type `T`.
+    ///
+    /// On error the reader is left unchanged.
+    #[inline]
+    pub fn read<T: BitWidth>(&mut self) -> Result<T, StreamError> {
+        self.read_bits(T::WIDTH).map(T::from_bits_truncate)
+    }
+
+    /// Reads a single bit as a bool.
+    #[inline]
+    pub fn read_bool(&mut self) -> Result<bool, StreamError> {
+        self.read::<U1>().map(|b| b == U1::B1)
+    }
+
+    /// Skips `bits` bits.
+    ///
+    /// On error the reader is left unchanged.
+    #[inline]
+    pub fn skip(&mut self, bits: usize) -> Result<(), StreamError> {
+        if self.remaining() < bits {
+            return Err(StreamError::Underrun);
+        }
+        self.pos += bits;
+        Ok(())
+    }
+
+    /// Skips to the next byte boundary, if the reader is not already at one.
+    #[inline]
+    pub fn align_to_byte(&mut self) {
+        self.pos = self.pos.next_multiple_of(8);
+    }
+
+    /// Returns the bytes after the current position, starting at the next byte boundary.
+    #[inline]
+    pub fn rest(&self) -> &'a [u8] {
+        &self.buf[self.pos.div_ceil(8)..]
+    }
+}
+
+/// Writes a sequence of bit fields into a fixed byte buffer.
+///
+/// Only the bits that are written are changed; unwritten bits keep their contents. Writes
+/// past the end of the buffer return `StreamError::Overflow` rather than panicking.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut buf = [0u8; 3];
+/// let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+/// w.write(U3::B101).unwrap();
+/// w.write(U5::B10110).unwrap();
+/// w.write(U12::from(0x5afu16)).unwrap();
+/// w.write_bool(true).unwrap();
+/// assert_eq!(w.as_bytes(), [0b1011_0110, 0b0101_1010, 0b1111_1000]);
+/// assert_eq!(w.write(U4::B0000), Err(StreamError::Overflow));
+/// ```
+#[derive(Debug)]
+pub struct BitWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+    order: StreamOrder,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Constructs a writer positioned at the start of `buf`.
+    #[inline]
+    pub fn new(buf: &'a mut [u8], order: StreamOrder) -> Self {
+        BitWriter { buf, pos: 0, order }
+    }
+
+    /// Returns the order in which bits are written.
+    #[inline]
+    pub fn order(&self) -> StreamOrder {
+        self.order
+    }
+
+    /// Returns the number of bits written or skipped so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the number of bits left to write.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.buf.len() * 8 - self.pos
+    }
+
+    /// Returns `true` if the writer is at a byte boundary.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.pos % 8 == 0
+    }
+
+    /// Writes the low `width` bits of `value`.
+    ///
+    /// On error the writer and the buffer are left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32.
+    #[inline]
+    pub fn write_bits(&mut self, value: u32, width: u32) -> Result<(), StreamError> {
+        if self.remaining() < width as usize {
+            return Err(StreamError::Overflow);
+        }
+        self.buf.set_bits(self.pos, width, value, self.order.bit_order());
+        self.pos += width as usize;
+        Ok(())
+    }
+
+    /// Writes a field of type `T`.
+    ///
+    /// On error the writer and the buffer are left unchanged.
+    #[inline]
+    pub fn write<T: BitWidth>(&mut self, value: T) -> Result<(), StreamError> {
+        self.write_bits(value.bits(), T::WIDTH)
+    }
+
+    /// Writes a single bit from a bool.
+    #[inline]
+    pub fn write_bool(&mut self, value: bool) -> Result<(), StreamError> {
+        self.write(U1::from(value))
+    }
+
+    /// Writes zero bits up to the next byte boundary, if the writer is not already at one.
+    #[inline]
+    pub fn align_to_byte(&mut self) {
+        while !self.is_aligned() {
+            self.buf.set_field(self.pos, U1::B0, self.order.bit_order());
+            self.pos += 1;
+        }
+    }
+
+    /// Moves the writer back to the earlier position `pos`, so that a partly written value
+    /// can be abandoned. The bits already written past `pos` are left in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is past the current position.
+    #[inline]
+    pub fn rewind(&mut self, pos: usize) {
+        assert!(pos <= self.pos, "cannot rewind past the current position");
+        self.pos = pos;
+    }
+
+    /// Returns the bytes written so far, including a partially written last byte.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.pos.div_ceil(8)]
+    }
+}
+
+/// Writes a sequence of bit fields into a growable `Vec<u8>`.
+///
+/// ```
+/// use bobbin_bits::*;
+///
+/// let mut w = VecBitWriter::new(StreamOrder::LsbFirst);
+/// w.write(U3::B101);
+/// w.write(U12::from(0xabcu16));
+/// w.align_to_byte();
+/// assert_eq!(w.into_bytes(), [0b1110_0101, 0b0101_0101]);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct VecBitWriter {
+    buf: Vec<u8>,
+    pos: usize,
+    order: StreamOrder,
+}
+
+#[cfg(feature = "alloc")]
+impl VecBitWriter {
+    /// Constructs an empty writer.
+    #[inline]
+    pub fn new(order: StreamOrder) -> Self {
+        VecBitWriter { buf: Vec::new(), pos: 0, order }
+    }
+
+    /// Returns the number of bits written so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns `true` if the writer is at a byte boundary.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.pos % 8 == 0
+    }
+
+    /// Writes the low `width` bits of `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 32.
+    #[inline]
+    pub fn write_bits(&mut self, value: u32, width: u32) {
+        self.buf.resize((self.pos + width as usize).div_ceil(8), 0);
+        self.buf.set_bits(self.pos, width, value, self.order.bit_order());
+        self.pos += width as usize;
+    }
+
+    /// Writes a field of type `T`.
+    #[inline]
+    pub fn write<T: BitWidth>(&mut self, value: T) {
+        self.write_bits(value.bits(), T::WIDTH)
+    }
+
+    /// Writes a single bit from a bool.
+    #[inline]
+    pub fn write_bool(&mut self, value: bool) {
+        self.write(U1::from(value))
+    }
+
+    /// Writes zero bits up to the next byte boundary, if the writer is not already at one.
+    #[inline]
+    pub fn align_to_byte(&mut self) {
+        // Bytes are zeroed when they are added, so only the position moves.
+        self.pos = self.pos.next_multiple_of(8);
+    }
+
+    /// Returns the bytes written so far, including a partially written last byte.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Consumes the writer and returns the bytes written.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {U3, U4, U5, U7, U12, U32};
+
+    #[test]
+    fn test_reader_lsb_first() {
+        let mut r = BitReader::new(&[0b1110_0101, 0b0101_0101], StreamOrder::LsbFirst);
+        assert_eq!(r.read::<U3>(), Ok(U3::B101));
+        assert_eq!(r.peek::<U12>(), Ok(U12::from(0xabcu16)));
+        assert_eq!(r.read::<U12>(), Ok(U12::from(0xabcu16)));
+        assert_eq!(r.position(), 15);
+        assert_eq!(r.read_bool(), Ok(false));
+        assert_eq!(r.remaining(), 0);
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        let mut r = BitReader::new(&[0xff, 0x00, 0x12, 0x34], StreamOrder::MsbFirst);
+        assert_eq!(r.skip(7), Ok(()));
+        assert!(!r.is_aligned());
+        assert_eq!(r.read::<U32>(), Err(StreamError::Underrun));
+        assert_eq!(r.skip(26), Err(StreamError::Underrun));
+        // A failed read leaves the position unchanged.
+        assert_eq!(r.position(), 7);
+        r.align_to_byte();
+        r.align_to_byte();
+        assert_eq!(r.position(), 8);
+        assert_eq!(r.rest(), [0x00, 0x12, 0x34]);
+        assert_eq!(r.skip(24), Ok(()));
+        assert_eq!(r.read_bool(), Err(StreamError::Underrun));
+    }
+
+    #[test]
+    fn test_writer() {
+        let mut buf = [0xffu8; 2];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::LsbFirst);
+        w.write(U5::B00000).unwrap();
+        w.align_to_byte();
+        assert_eq!(w.write(U12::from(0u16)), Err(StreamError::Overflow));
+        w.write(U7::from(0x55u8)).unwrap();
+        assert_eq!(w.remaining(), 1);
+        assert_eq!(w.as_bytes(), [0x00, 0xd5]);
+        assert_eq!(buf, [0x00, 0xd5]);
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut buf = [0u8; 4];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        w.write_bits(0b101, 3).unwrap();
+        w.write_bits(0, 0).unwrap();
+        w.write_bits(0x1fff_ffff, 29).unwrap();
+        assert_eq!(w.write_bits(0, 1), Err(StreamError::Overflow));
+        assert_eq!(buf, [0xbf, 0xff, 0xff, 0xff]);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(r.read_bits(3), Ok(0b101));
+        assert_eq!(r.peek_bits(29), Ok(0x1fff_ffff));
+        assert_eq!(r.read_bits(30), Err(StreamError::Underrun));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &order in &[StreamOrder::MsbFirst, StreamOrder::LsbFirst] {
+            let mut buf = [0u8; 8];
+            let mut w = BitWriter::new(&mut buf, order);
+            for i in 0..12u8 {
+                w.write(U5::from(i)).unwrap();
+            }
+            w.write(U4::B1001).unwrap();
+            assert_eq!(w.write_bool(true), Err(StreamError::Overflow));
+
+            let mut r = BitReader::new(&buf, order);
+            for i in 0..12u8 {
+                assert_eq!(r.read::<U5>(), Ok(U5::from(i)));
+            }
+            assert_eq!(r.read::<U4>(), Ok(U4::B1001));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_vec_writer() {
+        let mut w = VecBitWriter::new(StreamOrder::MsbFirst);
+        w.write(U3::B101);
+        w.write_bool(true);
+        w.align_to_byte();
+        w.write(U32::from(0x1234_5678u32));
+        w.write_bool(true);
+        assert_eq!(w.position(), 41);
+        assert_eq!(w.as_bytes(), [0xb0, 0x12, 0x34, 0x56, 0x78, 0x80]);
+    }
+}
@@ -1,35 +1,118 @@
 macro_rules! impl_u8 {
-    ($B:ident, $m:expr) => (
+    ($B:ident, $width:expr, $m:expr) => (
         #[derive(PartialEq, Eq, Clone, Copy)]
+        #[repr(transparent)]
         pub struct $B(u8);
 
         impl $B {
             #[inline]
             /// Returns the primitive representation of the value.            
-            pub fn value(&self) -> u8 {
+            pub const fn value(&self) -> u8 {
                 self.0
             }
 
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a slice of primitives as a slice of values, after checking that
+            /// every element is within the range of the type.
+            pub fn try_cast_slice(slice: &[u8]) -> Result<&[Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a mutable slice of primitives as a slice of values, after checking
+            /// that every element is within the range of the type.
+            pub fn try_cast_slice_mut(slice: &mut [u8]) -> Result<&mut [Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a slice of values as a slice of their primitive representations.
+            pub fn as_primitive_slice(slice: &[Self]) -> &[u8] {
+                unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const u8, slice.len()) }
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` little-endian bytes.
+            pub fn to_le_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_le_bytes()[..usize::div_ceil($width, 8)]);
+                bytes
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` big-endian bytes.
+            pub fn to_be_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_be_bytes()[1 - usize::div_ceil($width, 8)..]);
+                bytes
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` little-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_le_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 1];
+                all[..usize::div_ceil($width, 8)].copy_from_slice(&bytes);
+                let v = u8::from_le_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` big-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_be_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 1];
+                all[1 - usize::div_ceil($width, 8)..].copy_from_slice(&bytes);
+                let v = u8::from_be_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u8_unchecked(other: u8) -> Self {
                 $B(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u16_unchecked(other: u16) -> Self {
                 $B(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u32_unchecked(other: u32) -> Self {
                 $B(other as u8)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_usize_unchecked(other: usize) -> Self {
                 $B(other as u8)
             }
@@ -140,6 +223,21 @@ macro_rules! impl_u8 {
             }
         }
 
+        impl BitWidth for $B {
+            const WIDTH: u32 = $width;
+            const MASK: u32 = $m;
+
+            #[inline]
+            fn bits(self) -> u32 {
+                self.0 as u32
+            }
+
+            #[inline]
+            fn from_bits_truncate(bits: u32) -> Self {
+                $B((bits & $m) as u8)
+            }
+        }
+
         impl fmt::Debug for $B {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 if $m <= 0b1111 {
@@ -169,37 +267,120 @@ macro_rules! impl_u8 {
 
 
 macro_rules! impl_u16 {
-    ($B:ident, $m:expr) => (
+    ($B:ident, $width:expr, $m:expr) => (
         #[derive(PartialEq, Eq, Clone, Copy)]
+        #[repr(transparent)]
         pub struct $B(u16);
 
         impl $B {
             #[inline]
             /// Returns the primitive representation of the value.            
-            pub fn value(&self) -> u16 {
+            pub const fn value(&self) -> u16 {
                 self.0
             }
 
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a slice of primitives as a slice of values, after checking that
+            /// every element is within the range of the type.
+            pub fn try_cast_slice(slice: &[u16]) -> Result<&[Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a mutable slice of primitives as a slice of values, after checking
+            /// that every element is within the range of the type.
+            pub fn try_cast_slice_mut(slice: &mut [u16]) -> Result<&mut [Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a slice of values as a slice of their primitive representations.
+            pub fn as_primitive_slice(slice: &[Self]) -> &[u16] {
+                unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const u16, slice.len()) }
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` little-endian bytes.
+            pub fn to_le_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_le_bytes()[..usize::div_ceil($width, 8)]);
+                bytes
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` big-endian bytes.
+            pub fn to_be_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_be_bytes()[2 - usize::div_ceil($width, 8)..]);
+                bytes
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` little-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_le_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 2];
+                all[..usize::div_ceil($width, 8)].copy_from_slice(&bytes);
+                let v = u16::from_le_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` big-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_be_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 2];
+                all[2 - usize::div_ceil($width, 8)..].copy_from_slice(&bytes);
+                let v = u16::from_be_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u8_unchecked(other: u8) -> Self {
                 $B(other as u16)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u16_unchecked(other: u16) -> Self {
                 $B(other as u16)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u32_unchecked(other: u32) -> Self {
                 $B(other as u16)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_usize_unchecked(other: usize) -> Self {
                 $B(other as u16)
             }        
@@ -255,6 +436,7 @@ macro_rules! impl_u16 {
 
         impl From<u16> for $B {
             #[inline]
+            #[allow(clippy::bad_bit_mask)]
             fn from(other: u16) -> Self {
                 assert!(other & !$m == 0);
                 $B(other as u16)
@@ -304,6 +486,21 @@ macro_rules! impl_u16 {
             }
         }
 
+        impl BitWidth for $B {
+            const WIDTH: u32 = $width;
+            const MASK: u32 = $m;
+
+            #[inline]
+            fn bits(self) -> u32 {
+                self.0 as u32
+            }
+
+            #[inline]
+            fn from_bits_truncate(bits: u32) -> Self {
+                $B((bits & $m) as u16)
+            }
+        }
+
         impl fmt::Debug for $B {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 if $m <= 0b1111_1111_1111 {
@@ -333,37 +530,120 @@ macro_rules! impl_u16 {
 
 
 macro_rules! impl_u32 {
-    ($B:ident, $m:expr) => (
+    ($B:ident, $width:expr, $m:expr) => (
         #[derive(PartialEq, Eq, Clone, Copy)]
+        #[repr(transparent)]
         pub struct $B(u32);
 
         impl $B {
             #[inline]
             /// Returns the primitive representation of the value.            
-            pub fn value(&self) -> u32 {
+            pub const fn value(&self) -> u32 {
                 self.0
             }
 
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a slice of primitives as a slice of values, after checking that
+            /// every element is within the range of the type.
+            pub fn try_cast_slice(slice: &[u32]) -> Result<&[Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Reinterprets a mutable slice of primitives as a slice of values, after checking
+            /// that every element is within the range of the type.
+            pub fn try_cast_slice_mut(slice: &mut [u32]) -> Result<&mut [Self], RangeError> {
+                match slice.iter().position(|&v| v & !$m != 0) {
+                    Some(index) => Err(RangeError { index }),
+                    None => Ok(unsafe { ::core::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut Self, slice.len()) }),
+                }
+            }
+
+            #[inline]
+            /// Reinterprets a slice of values as a slice of their primitive representations.
+            pub fn as_primitive_slice(slice: &[Self]) -> &[u32] {
+                unsafe { ::core::slice::from_raw_parts(slice.as_ptr() as *const u32, slice.len()) }
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` little-endian bytes.
+            pub fn to_le_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_le_bytes()[..usize::div_ceil($width, 8)]);
+                bytes
+            }
+
+            #[inline]
+            /// Returns the value as `ceil(WIDTH / 8)` big-endian bytes.
+            pub fn to_be_bytes(self) -> [u8; usize::div_ceil($width, 8)] {
+                let mut bytes = [0; usize::div_ceil($width, 8)];
+                bytes.copy_from_slice(&self.value().to_be_bytes()[4 - usize::div_ceil($width, 8)..]);
+                bytes
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` little-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_le_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 4];
+                all[..usize::div_ceil($width, 8)].copy_from_slice(&bytes);
+                let v = u32::from_le_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
+            #[inline]
+            #[allow(clippy::bad_bit_mask)]
+            /// Constructs the value from `ceil(WIDTH / 8)` big-endian bytes, or returns
+            /// `RangeError { index: 0 }` if any of the padding bits above `WIDTH` are set.
+            pub fn from_be_bytes(bytes: [u8; usize::div_ceil($width, 8)]) -> Result<Self, RangeError> {
+                let mut all = [0; 4];
+                all[4 - usize::div_ceil($width, 8)..].copy_from_slice(&bytes);
+                let v = u32::from_be_bytes(all);
+                if v & !$m != 0 { Err(RangeError { index: 0 }) } else { Ok($B(v)) }
+            }
+
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u8_unchecked(other: u8) -> Self {
                 $B(other as u32)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u16_unchecked(other: u16) -> Self {
                 $B(other as u32)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_u32_unchecked(other: u32) -> Self {
                 $B(other as u32)
             }
 
             #[inline]
             /// Constructs the value without a range check.
+            ///
+            /// # Safety
+            ///
+            /// `other` must be within the range of the type.
             pub unsafe fn from_usize_unchecked(other: usize) -> Self {
                 $B(other as u32)
             }
@@ -383,6 +663,7 @@ macro_rules! impl_u32 {
 
         impl From<i32> for $B {
             #[inline]
+            #[allow(clippy::bad_bit_mask)]
             fn from(other: i32) -> Self {
                 assert!(other >= 0);
                 assert!(other as u32 & !$m == 0);
@@ -400,6 +681,7 @@ macro_rules! impl_u32 {
 
         impl From<u32> for $B {
             #[inline]
+            #[allow(clippy::bad_bit_mask)]
             fn from(other: u32) -> Self {
                 assert!(other & !$m == 0);
                 $B(other as u32)
@@ -457,6 +739,21 @@ macro_rules! impl_u32 {
             }
         }
 
+        impl BitWidth for $B {
+            const WIDTH: u32 = $width;
+            const MASK: u32 = $m;
+
+            #[inline]
+            fn bits(self) -> u32 {
+                self.0 as u32
+            }
+
+            #[inline]
+            fn from_bits_truncate(bits: u32) -> Self {
+                $B((bits & $m) as u32)
+            }
+        }
+
         impl fmt::Debug for $B {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 if $m <= 0b1111_1111_1111_1111_1111u32 {
@@ -0,0 +1,491 @@
+//! ASN.1 unaligned PER (X.691 UPER) encoding.
+//!
+//! UPER encodes a constrained whole number in the fewest bits that can hold its range,
+//! most significant bit first and without padding, so a U type is encoded in exactly
+//! its width and an R type in `ceil(log2(N))` bits: an `R12` takes 4 bits, a `U7` 7 and
+//! an `R1` none at all.
+//!
+//! The `Uper` trait is implemented for the U and R types, `bool`, fixed-size arrays
+//! (`SEQUENCE (SIZE(N)) OF`) and tuples (a `SEQUENCE` without optional components). An
+//! `ENUMERATED` type with `n` root items is encoded as its index, like an `Rn`. Other
+//! constrained integers and bit strings are handled by the free functions.
+//!
+//! Readers and writers must be `StreamOrder::MsbFirst`; encoding or decoding with any
+//! other order returns `UperError::StreamOrder`. Extensible types, unconstrained integers
+//! and lengths of 16K or more are not supported.
+//!
+//! ```
+//! use bobbin_bits::*;
+//! use bobbin_bits::uper::*;
+//!
+//! // SEQUENCE { kind ENUMERATED { a, b, c }, urgent BOOLEAN, speed INTEGER (0..8191) }
+//! let msg = (R3::X2, true, U13::from(300u16));
+//!
+//! let mut buf = [0u8; 2];
+//! let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+//! msg.encode(&mut w).unwrap();
+//! assert_eq!(w.position(), 16);
+//! assert_eq!(buf, [0b1010_0001, 0b0010_1100]);
+//!
+//! let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+//! assert_eq!(<(R3, bool, U13)>::decode(&mut r), Ok(msg));
+//! ```
+
+use core::fmt;
+
+use stream::{BitReader, BitWriter, StreamError, StreamOrder};
+use {R1, R2, R3, R4, R5, R6, R7, R8, R9, R10, R11, R12, R13, R14, R15, R16};
+use {R17, R18, R19, R20, R21, R22, R23, R24, R25, R26, R27, R28, R29, R30, R31, R32, R33};
+use {U1, U2, U3, U4, U5, U6, U7, U8, U9, U10, U11, U12, U13, U14, U15, U16};
+use {U17, U18, U19, U20, U21, U22, U23, U24, U25, U26, U27, U28, U29, U30, U31, U32};
+
+/// An error encoding or decoding UPER.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UperError {
+    /// The input ended before the value was decoded.
+    Underrun,
+    /// The output buffer has no room for the value.
+    Overflow,
+    /// A value is outside of its constraint.
+    OutOfRange,
+    /// The reader or writer is not `StreamOrder::MsbFirst`.
+    StreamOrder,
+}
+
+impl From<StreamError> for UperError {
+    #[inline]
+    fn from(other: StreamError) -> Self {
+        match other {
+            StreamError::Underrun => UperError::Underrun,
+            StreamError::Overflow => UperError::Overflow,
+        }
+    }
+}
+
+impl fmt::Display for UperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UperError::Underrun => f.write_str("unexpected end of input"),
+            UperError::Overflow => f.write_str("output buffer is full"),
+            UperError::OutOfRange => f.write_str("value is outside of its constraint"),
+            UperError::StreamOrder => f.write_str("stream is not most significant bit first"),
+        }
+    }
+}
+
+/// A type with a UPER encoding.
+pub trait Uper: Sized {
+    /// Encodes the value.
+    ///
+    /// On error the writer is left at its original position, although bits of the
+    /// components already encoded may have been written past it.
+    fn encode(&self, w: &mut BitWriter) -> Result<(), UperError>;
+
+    /// Decodes a value.
+    fn decode(r: &mut BitReader) -> Result<Self, UperError>;
+}
+
+/// Returns the number of bits used to encode a constrained whole number with `range`
+/// possible values.
+///
+/// ```
+/// use bobbin_bits::uper::range_bits;
+///
+/// assert_eq!(range_bits(1), 0);
+/// assert_eq!(range_bits(2), 1);
+/// assert_eq!(range_bits(12), 4);
+/// assert_eq!(range_bits(256), 8);
+/// assert_eq!(range_bits(257), 9);
+/// ```
+#[inline]
+pub const fn range_bits(range: u64) -> u32 {
+    if range <= 1 {
+        0
+    } else {
+        64 - (range - 1).leading_zeros()
+    }
+}
+
+/// Returns `StreamOrder` unless `order` is `MsbFirst`, the only order UPER is defined in.
+#[inline]
+fn check_order(order: StreamOrder) -> Result<(), UperError> {
+    match order {
+        StreamOrder::MsbFirst => Ok(()),
+        StreamOrder::LsbFirst => Err(UperError::StreamOrder),
+    }
+}
+
+/// Writes the low `width` bits of `value`, for `width` up to 64.
+fn write_wide(w: &mut BitWriter, value: u64, width: u32) -> Result<(), UperError> {
+    if w.remaining() < width as usize {
+        return Err(UperError::Overflow);
+    }
+    if width > 32 {
+        w.write_bits((value >> 32) as u32, width - 32)?;
+        w.write_bits(value as u32, 32)?;
+    } else {
+        w.write_bits(value as u32, width)?;
+    }
+    Ok(())
+}
+
+/// Reads `width` bits, for `width` up to 64.
+fn read_wide(r: &mut BitReader, width: u32) -> Result<u64, UperError> {
+    if r.remaining() < width as usize {
+        return Err(UperError::Underrun);
+    }
+    if width > 32 {
+        let hi = r.read_bits(width - 32)? as u64;
+        Ok(hi << 32 | r.read_bits(32)? as u64)
+    } else {
+        Ok(r.read_bits(width)? as u64)
+    }
+}
+
+/// Encodes `value` as a whole number constrained to `lb..=ub`.
+///
+/// Returns `OutOfRange` if `value` is outside of the constraint.
+///
+/// # Panics
+///
+/// Panics if `lb` is greater than `ub`.
+pub fn write_constrained(w: &mut BitWriter, value: i64, lb: i64, ub: i64) -> Result<(), UperError> {
+    assert!(lb <= ub, "empty constraint");
+    check_order(w.order())?;
+    if value < lb || value > ub {
+        return Err(UperError::OutOfRange);
+    }
+    let range = (ub as i128 - lb as i128) as u64;
+    write_wide(w, (value as i128 - lb as i128) as u64, 64 - range.leading_zeros())
+}
+
+/// Decodes a whole number constrained to `lb..=ub`.
+///
+/// Returns `OutOfRange` if the encoded offset is past `ub`.
+///
+/// # Panics
+///
+/// Panics if `lb` is greater than `ub`.
+pub fn read_constrained(r: &mut BitReader, lb: i64, ub: i64) -> Result<i64, UperError> {
+    assert!(lb <= ub, "empty constraint");
+    check_order(r.order())?;
+    let range = (ub as i128 - lb as i128) as u64;
+    let start = r.clone();
+    let offset = read_wide(r, 64 - range.leading_zeros())?;
+    if offset > range {
+        *r = start;
+        return Err(UperError::OutOfRange);
+    }
+    Ok((lb as i128 + offset as i128) as i64)
+}
+
+/// The largest upper bound of a length that is encoded as a constrained whole number.
+const MAX_LENGTH: usize = 16 * 1024 - 1;
+
+/// Encodes the first `len` bits of `bits`, most significant bit of the first byte first,
+/// as a `BIT STRING (SIZE(lb..ub))`.
+///
+/// A length determinant is written only if `lb` and `ub` differ.
+///
+/// # Panics
+///
+/// Panics if `lb` is greater than `ub`, `ub` is 16K or more, or `bits` holds fewer than
+/// `len` bits.
+pub fn write_bit_string(w: &mut BitWriter, bits: &[u8], len: usize, lb: usize, ub: usize) -> Result<(), UperError> {
+    assert!(lb <= ub && ub <= MAX_LENGTH, "unsupported size constraint");
+    assert!(len <= bits.len() * 8, "bit string is shorter than its length");
+    check_order(w.order())?;
+    if len < lb || len > ub {
+        return Err(UperError::OutOfRange);
+    }
+    if w.remaining() < range_bits((ub - lb + 1) as u64) as usize + len {
+        return Err(UperError::Overflow);
+    }
+    write_constrained(w, len as i64, lb as i64, ub as i64)?;
+    let mut src = BitReader::new(bits, StreamOrder::MsbFirst);
+    for _ in 0..len / 8 {
+        w.write_bits(src.read_bits(8)?, 8)?;
+    }
+    w.write_bits(src.read_bits((len % 8) as u32)?, (len % 8) as u32)?;
+    Ok(())
+}
+
+/// Decodes a `BIT STRING (SIZE(lb..ub))` into `out`, most significant bit of the first
+/// byte first, returning its length in bits.
+///
+/// Bits of `out` past the length are left unchanged.
+///
+/// # Panics
+///
+/// Panics if `lb` is greater than `ub`, `ub` is 16K or more, or `out` is too small to
+/// hold `ub` bits.
+pub fn read_bit_string(r: &mut BitReader, out: &mut [u8], lb: usize, ub: usize) -> Result<usize, UperError> {
+    assert!(lb <= ub && ub <= MAX_LENGTH, "unsupported size constraint");
+    assert!(ub <= out.len() * 8, "output is shorter than the size constraint");
+    check_order(r.order())?;
+    let start = r.clone();
+    let len = read_constrained(r, lb as i64, ub as i64)? as usize;
+    if r.remaining() < len {
+        *r = start;
+        return Err(UperError::Underrun);
+    }
+    let mut dst = BitWriter::new(out, StreamOrder::MsbFirst);
+    for _ in 0..len / 8 {
+        dst.write_bits(r.read_bits(8)?, 8)?;
+    }
+    dst.write_bits(r.read_bits((len % 8) as u32)?, (len % 8) as u32)?;
+    Ok(len)
+}
+
+impl Uper for bool {
+    #[inline]
+    fn encode(&self, w: &mut BitWriter) -> Result<(), UperError> {
+        check_order(w.order())?;
+        Ok(w.write_bool(*self)?)
+    }
+
+    #[inline]
+    fn decode(r: &mut BitReader) -> Result<Self, UperError> {
+        check_order(r.order())?;
+        Ok(r.read_bool()?)
+    }
+}
+
+macro_rules! impl_uper_u {
+    ($($t:ident),*) => ($(
+        impl Uper for $t {
+            #[inline]
+            fn encode(&self, w: &mut BitWriter) -> Result<(), UperError> {
+                check_order(w.order())?;
+                Ok(w.write(*self)?)
+            }
+
+            #[inline]
+            fn decode(r: &mut BitReader) -> Result<Self, UperError> {
+                check_order(r.order())?;
+                Ok(r.read()?)
+            }
+        }
+    )*)
+}
+
+impl_uper_u!(U1, U2, U3, U4, U5, U6, U7, U8, U9, U10, U11, U12, U13, U14, U15, U16);
+impl_uper_u!(U17, U18, U19, U20, U21, U22, U23, U24, U25, U26, U27, U28, U29, U30, U31, U32);
+
+macro_rules! impl_uper_r {
+    ($($t:ident, $n:expr;)*) => ($(
+        impl Uper for $t {
+            #[inline]
+            fn encode(&self, w: &mut BitWriter) -> Result<(), UperError> {
+                check_order(w.order())?;
+                Ok(w.write_bits(self.value() as u32, range_bits($n))?)
+            }
+
+            #[inline]
+            fn decode(r: &mut BitReader) -> Result<Self, UperError> {
+                check_order(r.order())?;
+                let start = r.clone();
+                let value = r.read_bits(range_bits($n))?;
+                if value >= $n {
+                    *r = start;
+                    return Err(UperError::OutOfRange);
+                }
+                Ok($t::from(value))
+            }
+        }
+    )*)
+}
+
+impl_uper_r! {
+    R1, 1; R2, 2; R3, 3; R4, 4; R5, 5; R6, 6; R7, 7; R8, 8;
+    R9, 9; R10, 10; R11, 11; R12, 12; R13, 13; R14, 14; R15, 15; R16, 16;
+    R17, 17; R18, 18; R19, 19; R20, 20; R21, 21; R22, 22; R23, 23; R24, 24;
+    R25, 25; R26, 26; R27, 27; R28, 28; R29, 29; R30, 30; R31, 31; R32, 32;
+    R33, 33;
+}
+
+impl<T: Uper, const N: usize> Uper for [T; N] {
+    #[inline]
+    fn encode(&self, w: &mut BitWriter) -> Result<(), UperError> {
+        check_order(w.order())?;
+        let start = w.position();
+        self.iter().try_for_each(|v| v.encode(w)).inspect_err(|_| w.rewind(start))
+    }
+
+    #[inline]
+    fn decode(r: &mut BitReader) -> Result<Self, UperError> {
+        check_order(r.order())?;
+        let start = r.clone();
+        let mut out: [Option<T>; N] = [const { None }; N];
+        for o in out.iter_mut() {
+            match T::decode(r) {
+                Ok(v) => *o = Some(v),
+                Err(e) => {
+                    *r = start;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(out.map(|o| o.unwrap()))
+    }
+}
+
+macro_rules! impl_uper_tuple {
+    ($($t:ident $v:ident),*) => {
+        impl<$($t: Uper),*> Uper for ($($t,)*) {
+            #[inline]
+            fn encode(&self, w: &mut BitWriter) -> Result<(), UperError> {
+                check_order(w.order())?;
+                let start = w.position();
+                let ($(ref $v,)*) = *self;
+                let encode = |w: &mut BitWriter| -> Result<(), UperError> {
+                    $($v.encode(w)?;)*
+                    Ok(())
+                };
+                encode(w).inspect_err(|_| w.rewind(start))
+            }
+
+            #[inline]
+            fn decode(r: &mut BitReader) -> Result<Self, UperError> {
+                check_order(r.order())?;
+                let start = r.clone();
+                let decode = |r: &mut BitReader| Ok(($($t::decode(r)?,)*));
+                decode(r).inspect_err(|_| *r = start)
+            }
+        }
+    };
+}
+
+impl_uper_tuple!(A a);
+impl_uper_tuple!(A a, B b);
+impl_uper_tuple!(A a, B b, C c);
+impl_uper_tuple!(A a, B b, C c, D d);
+impl_uper_tuple!(A a, B b, C c, D d, E e);
+impl_uper_tuple!(A a, B b, C c, D d, E e, F f);
+impl_uper_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_uper_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes with `f` into an 8 byte buffer, returning the buffer and the number of bits written.
+    fn encode<F: FnOnce(&mut BitWriter) -> Result<(), UperError>>(f: F) -> ([u8; 8], usize) {
+        let mut buf = [0u8; 8];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        f(&mut w).unwrap();
+        let bits = w.position();
+        (buf, bits)
+    }
+
+    #[test]
+    fn test_constrained() {
+        // INTEGER (-5..10) value 0 is the offset 5 in 4 bits.
+        assert_eq!(encode(|w| write_constrained(w, 0, -5, 10)), ([0b0101_0000, 0, 0, 0, 0, 0, 0, 0], 4));
+        // INTEGER (3..6) value 6 is '11'B.
+        assert_eq!(encode(|w| write_constrained(w, 6, 3, 6)).1, 2);
+        // A single-valued INTEGER (1000..1000) takes no bits.
+        assert_eq!(encode(|w| write_constrained(w, 1000, 1000, 1000)).1, 0);
+        // INTEGER (0..4294967295) takes 32 bits, and the full i64 range 64.
+        assert_eq!(encode(|w| write_constrained(w, 1, 0, 4_294_967_295)).1, 32);
+        let (buf, bits) = encode(|w| write_constrained(w, -1, i64::MIN, i64::MAX));
+        assert_eq!((buf, bits), ([0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], 64));
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        assert_eq!(read_constrained(&mut r, i64::MIN, i64::MAX), Ok(-1));
+
+        let mut buf = [0u8; 1];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        assert_eq!(write_constrained(&mut w, 11, -5, 10), Err(UperError::OutOfRange));
+        assert_eq!(write_constrained(&mut w, 256, 0, 256), Err(UperError::Overflow));
+        assert_eq!(w.position(), 0);
+
+        // INTEGER (0..4) uses 3 bits, so '111'B is out of range.
+        let mut r = BitReader::new(&[0b1110_0000], StreamOrder::MsbFirst);
+        assert_eq!(read_constrained(&mut r, 0, 4), Err(UperError::OutOfRange));
+        assert_eq!(r.position(), 0);
+    }
+
+    #[test]
+    fn test_u_and_r() {
+        assert_eq!(encode(|w| R12::Xb.encode(w)), ([0b1011_0000, 0, 0, 0, 0, 0, 0, 0], 4));
+        assert_eq!(encode(|w| U7::from(0x55u8).encode(w)), ([0b1010_1010, 0, 0, 0, 0, 0, 0, 0], 7));
+        assert_eq!(encode(|w| R1::X0.encode(w)).1, 0);
+        assert_eq!(encode(|w| R32::X1f.encode(w)).1, 5);
+        assert_eq!(encode(|w| R17::X10.encode(w)).1, 5);
+
+        let mut r = BitReader::new(&[0b1100_0000], StreamOrder::MsbFirst);
+        assert_eq!(R12::decode(&mut r), Err(UperError::OutOfRange));
+        assert_eq!(R4::decode(&mut r), Ok(R4::X3));
+        assert_eq!(U7::decode(&mut r), Err(UperError::Underrun));
+    }
+
+    #[test]
+    fn test_bit_string() {
+        // BIT STRING (SIZE(4)) '1011'B has no length determinant.
+        assert_eq!(encode(|w| write_bit_string(w, &[0b1011_0000], 4, 4, 4)), ([0b1011_0000, 0, 0, 0, 0, 0, 0, 0], 4));
+        // BIT STRING (SIZE(0..7)) '101'B is the length 3 in 3 bits followed by the bits.
+        assert_eq!(encode(|w| write_bit_string(w, &[0b1010_0000], 3, 0, 7)), ([0b0111_0100, 0, 0, 0, 0, 0, 0, 0], 6));
+        // Strings longer than a byte are copied across byte boundaries.
+        let (buf, bits) = encode(|w| {
+            true.encode(w)?;
+            write_bit_string(w, &[0xde, 0xad, 0xbe, 0xef], 28, 20, 35)
+        });
+        assert_eq!(bits, 1 + 4 + 28);
+        assert_eq!(buf[..5], [0b1100_0110, 0xf5, 0x6d, 0xf7, 0x00]);
+
+        let mut r = BitReader::new(&buf, StreamOrder::MsbFirst);
+        let mut out = [0u8; 5];
+        assert_eq!(bool::decode(&mut r), Ok(true));
+        assert_eq!(read_bit_string(&mut r, &mut out, 20, 35), Ok(28));
+        assert_eq!(out, [0xde, 0xad, 0xbe, 0xe0, 0x00]);
+
+        let mut buf = [0u8; 1];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        assert_eq!(write_bit_string(&mut w, &[0xff], 8, 0, 8), Err(UperError::Overflow));
+        assert_eq!(write_bit_string(&mut w, &[0xff], 8, 0, 7), Err(UperError::OutOfRange));
+        assert_eq!(w.position(), 0);
+    }
+
+    #[test]
+    fn test_sequence() {
+        // SEQUENCE { a INTEGER (0..7), b BOOLEAN, c INTEGER (0..1023) } { a 5, b TRUE, c 300 }
+        let value = (R8::X5, true, U10::from(300u16));
+        let (buf, bits) = encode(|w| value.encode(w));
+        assert_eq!((buf[..2].to_vec(), bits), (std::vec![0b1011_0100, 0b1011_0000], 14));
+
+        // SEQUENCE (SIZE(3)) OF INTEGER (0..2)
+        let (buf, bits) = encode(|w| [R3::X0, R3::X2, R3::X1].encode(w));
+        assert_eq!((buf[0], bits), (0b0010_0100, 6));
+
+        let mut r = BitReader::new(&[0b1011_0100, 0b1011_0011], StreamOrder::MsbFirst);
+        assert_eq!(<(R8, bool, U10)>::decode(&mut r), Ok(value));
+        assert_eq!(<[R3; 2]>::decode(&mut r), Err(UperError::OutOfRange));
+        assert_eq!(r.position(), 14);
+
+        // A sequence that does not fit leaves the writer where it was.
+        let mut buf = [0u8; 1];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::MsbFirst);
+        true.encode(&mut w).unwrap();
+        assert_eq!(value.encode(&mut w), Err(UperError::Overflow));
+        assert_eq!([U4::B1111; 2].encode(&mut w), Err(UperError::Overflow));
+        assert_eq!(w.position(), 1);
+    }
+
+    #[test]
+    fn test_stream_order() {
+        let mut buf = [0u8; 2];
+        let mut w = BitWriter::new(&mut buf, StreamOrder::LsbFirst);
+        assert_eq!(U3::B101.encode(&mut w), Err(UperError::StreamOrder));
+        assert_eq!((R4::X1, false).encode(&mut w), Err(UperError::StreamOrder));
+        assert_eq!(write_constrained(&mut w, 1, 0, 3), Err(UperError::StreamOrder));
+        assert_eq!(write_bit_string(&mut w, &[0xff], 8, 8, 8), Err(UperError::StreamOrder));
+        assert_eq!(w.position(), 0);
+
+        let mut r = BitReader::new(&[0xff, 0xff], StreamOrder::LsbFirst);
+        assert_eq!(bool::decode(&mut r), Err(UperError::StreamOrder));
+        assert_eq!(<[R3; 2]>::decode(&mut r), Err(UperError::StreamOrder));
+        assert_eq!(read_constrained(&mut r, 0, 3), Err(UperError::StreamOrder));
+        assert_eq!(read_bit_string(&mut r, &mut [0; 1], 8, 8), Err(UperError::StreamOrder));
+        assert_eq!(r.position(), 0);
+    }
+}